@@ -0,0 +1,89 @@
+use std::process::Command;
+
+///gets the cpu affinity of a process as one bool per core
+///uses `taskset`, so only available on linux
+pub fn get_affinity(pid: usize, cpu_count: usize) -> Option<Vec<bool>> {
+    let output = Command::new("taskset")
+        .arg("-p")
+        .arg(pid.to_string())
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    //output looks like "pid 1234's current affinity mask: ff"
+    let text = String::from_utf8_lossy(&output.stdout);
+    let mask = text.trim().rsplit(": ").next()?;
+    parse_mask(mask, cpu_count)
+}
+
+///sets the cpu affinity of a process, returns the error output of taskset if it fails
+pub fn set_affinity(pid: usize, cores: &[bool]) -> Result<(), String> {
+    let output = Command::new("taskset")
+        .arg("-p")
+        .arg(format_mask(cores))
+        .arg(pid.to_string())
+        .output()
+        .map_err(|e| e.to_string())?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+    }
+}
+
+///hex mask with lowest core in lowest bit, may contain commas for large masks e.g. "ff,ffffffff"
+fn parse_mask(mask: &str, cpu_count: usize) -> Option<Vec<bool>> {
+    let digits: Vec<u32> = mask
+        .chars()
+        .filter(|c| *c != ',')
+        .rev()
+        .map(|c| c.to_digit(16))
+        .collect::<Option<_>>()?;
+    Some(
+        (0..cpu_count)
+            .map(|core| {
+                digits
+                    .get(core / 4)
+                    .is_some_and(|digit| digit & (1 << (core % 4)) != 0)
+            })
+            .collect(),
+    )
+}
+
+fn format_mask(cores: &[bool]) -> String {
+    let mut mask: String = cores
+        .chunks(4)
+        .map(|chunk| {
+            let digit = chunk
+                .iter()
+                .enumerate()
+                .filter(|(_, on)| **on)
+                .fold(0, |acc, (i, _)| acc | (1 << i));
+            char::from_digit(digit, 16).unwrap_or('0')
+        })
+        .collect::<String>()
+        .chars()
+        .rev()
+        .collect();
+    if mask.is_empty() {
+        mask.push('0');
+    }
+    mask
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mask_roundtrip() {
+        assert_eq!(parse_mask("5", 4), Some(vec![true, false, true, false]));
+        assert!(parse_mask("1,00000000", 33).unwrap()[32]);
+        assert_eq!(parse_mask("zz", 4), None);
+
+        let cores = vec![true, false, false, false, false, true];
+        assert_eq!(format_mask(&cores), "21");
+        assert_eq!(parse_mask(&format_mask(&cores), 6), Some(cores));
+    }
+}
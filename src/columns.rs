@@ -0,0 +1,272 @@
+use std::cmp::Ordering;
+
+use serde::{Deserialize, Deserializer};
+
+use crate::{
+    helpers::nice_size,
+    myprocess::{Metric, MyProcess},
+};
+
+///Column of the process table, stored in config by id
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Column {
+    Command,
+    Name,
+    Pid,
+    User,
+    Memory,
+    Children,
+    Total,
+    ///own value, or including descendants if tree_totals is set
+    Metric(Metric),
+    ///sum of all descendants
+    MetricChildren(Metric),
+    ///own value plus all descendants
+    MetricTotal(Metric),
+    Threads,
+    State,
+}
+
+impl Column {
+    ///every column, in picker order
+    pub const ALL: [Column; 33] = [
+        Column::Command,
+        Column::Name,
+        Column::Pid,
+        Column::User,
+        Column::Memory,
+        Column::Children,
+        Column::Total,
+        Column::Metric(Metric::Cpu),
+        Column::MetricChildren(Metric::Cpu),
+        Column::MetricTotal(Metric::Cpu),
+        Column::Metric(Metric::CpuTime),
+        Column::MetricChildren(Metric::CpuTime),
+        Column::MetricTotal(Metric::CpuTime),
+        Column::Metric(Metric::DiskRead),
+        Column::MetricChildren(Metric::DiskRead),
+        Column::MetricTotal(Metric::DiskRead),
+        Column::Metric(Metric::DiskWrite),
+        Column::MetricChildren(Metric::DiskWrite),
+        Column::MetricTotal(Metric::DiskWrite),
+        Column::Metric(Metric::DiskReadBytes),
+        Column::MetricChildren(Metric::DiskReadBytes),
+        Column::MetricTotal(Metric::DiskReadBytes),
+        Column::Metric(Metric::DiskWrittenBytes),
+        Column::MetricChildren(Metric::DiskWrittenBytes),
+        Column::MetricTotal(Metric::DiskWrittenBytes),
+        Column::Metric(Metric::NetRx),
+        Column::MetricChildren(Metric::NetRx),
+        Column::MetricTotal(Metric::NetRx),
+        Column::Metric(Metric::NetTx),
+        Column::MetricChildren(Metric::NetTx),
+        Column::MetricTotal(Metric::NetTx),
+        Column::Threads,
+        Column::State,
+    ];
+
+    ///shown if nothing is configured
+    pub const DEFAULT: [Column; 16] = [
+        Column::Command,
+        Column::Name,
+        Column::Pid,
+        Column::Memory,
+        Column::Children,
+        Column::Total,
+        Column::Metric(Metric::Cpu),
+        Column::Metric(Metric::CpuTime),
+        Column::Metric(Metric::DiskRead),
+        Column::Metric(Metric::DiskWrite),
+        Column::Metric(Metric::DiskReadBytes),
+        Column::Metric(Metric::DiskWrittenBytes),
+        Column::Metric(Metric::NetRx),
+        Column::Metric(Metric::NetTx),
+        Column::Threads,
+        Column::State,
+    ];
+
+    ///stable name used in config and views
+    pub fn id(&self) -> String {
+        match self {
+            Column::Command => "command".to_string(),
+            Column::Name => "name".to_string(),
+            Column::Pid => "pid".to_string(),
+            Column::User => "user".to_string(),
+            Column::Memory => "mem".to_string(),
+            Column::Children => "children".to_string(),
+            Column::Total => "total".to_string(),
+            Column::Metric(metric) => metric.key().to_string(),
+            Column::MetricChildren(metric) => format!("{}children", metric.key()),
+            Column::MetricTotal(metric) => format!("{}total", metric.key()),
+            Column::Threads => "threads".to_string(),
+            Column::State => "state".to_string(),
+        }
+    }
+
+    ///metric shown in the column, if any
+    pub fn metric(&self) -> Option<Metric> {
+        match self {
+            Column::Metric(metric)
+            | Column::MetricChildren(metric)
+            | Column::MetricTotal(metric) => Some(*metric),
+            _ => None,
+        }
+    }
+
+    ///column with this id, if any
+    pub fn from_id(id: &str) -> Option<Column> {
+        Column::ALL.into_iter().find(|c| c.id() == id)
+    }
+
+    ///reads an id, older configs stored the sort column as position in the original fixed columns
+    pub fn deserialize_id<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum IdOrIndex {
+            Id(String),
+            Index(usize),
+        }
+        Ok(match IdOrIndex::deserialize(deserializer)? {
+            IdOrIndex::Id(id) => id,
+            IdOrIndex::Index(i) => {
+                //disk was read and write combined, read is the closest
+                const LEGACY: [Column; 8] = [
+                    Column::Command,
+                    Column::Name,
+                    Column::Pid,
+                    Column::Memory,
+                    Column::Children,
+                    Column::Total,
+                    Column::Metric(Metric::Cpu),
+                    Column::Metric(Metric::DiskRead),
+                ];
+                LEGACY.get(i).unwrap_or(&Column::Command).id()
+            }
+        })
+    }
+
+    ///parses ids, ignoring unknown ones
+    pub fn from_ids(ids: &[String]) -> Vec<Column> {
+        ids.iter().filter_map(|id| Column::from_id(id)).collect()
+    }
+
+    ///title in the table header
+    pub fn header(&self) -> String {
+        match self {
+            Column::MetricChildren(metric) => format!("{} Ch", Column::Metric(*metric).header()),
+            Column::MetricTotal(metric) => format!("{} Tot", Column::Metric(*metric).header()),
+            _ => self.own_header().to_string(),
+        }
+    }
+
+    fn own_header(&self) -> &'static str {
+        match self {
+            Column::Command => "Command",
+            Column::Name => "Name",
+            Column::Pid => "PID",
+            Column::User => "User",
+            Column::Memory => "Self",
+            Column::Children => "Children",
+            Column::Total => "Total",
+            Column::Metric(Metric::Cpu) => "CPU",
+            Column::Metric(Metric::CpuTime) => "CPU Time",
+            Column::Metric(Metric::DiskRead) => "Read/s",
+            Column::Metric(Metric::DiskWrite) => "Write/s",
+            Column::Metric(Metric::DiskReadBytes) => "Read",
+            Column::Metric(Metric::DiskWrittenBytes) => "Written",
+            Column::Metric(Metric::NetRx) => "Net ↓",
+            Column::Metric(Metric::NetTx) => "Net ↑",
+            Column::Threads => "Threads",
+            Column::State => "State",
+            Column::MetricChildren(_) | Column::MetricTotal(_) => "",
+        }
+    }
+
+    ///width in the tui, command takes up the remaining space
+    pub fn width(&self) -> u16 {
+        match self {
+            Column::Command => 25,
+            Column::Name | Column::User => 15,
+            Column::MetricChildren(_) | Column::MetricTotal(_) => 12,
+            _ => 10,
+        }
+    }
+
+    ///numbers are right aligned
+    pub fn right_align(&self) -> bool {
+        !matches!(self, Column::Command | Column::Name | Column::User)
+    }
+
+    ///if tree_totals is set, metrics include all descendants
+    pub fn format(&self, proc: &MyProcess, tree_totals: bool) -> String {
+        match self {
+            Column::Command => proc.command_display.clone(),
+            Column::Name => proc.name.clone(),
+            Column::Pid => proc.pid.to_string(),
+            Column::User => proc.user.clone(),
+            Column::Memory => nice_size(proc.memory),
+            Column::Children => nice_size(proc.children_memory),
+            Column::Total => nice_size(proc.total()),
+            Column::Metric(metric) => metric.format(metric_value(proc, *metric, tree_totals)),
+            Column::MetricChildren(metric) => metric.format(proc.children(*metric)),
+            Column::MetricTotal(metric) => metric.format(proc.total_usage(*metric)),
+            Column::Threads if proc.threads == 0 => String::new(),
+            Column::Threads => proc.threads.to_string(),
+            Column::State => proc.state.label().to_string(),
+        }
+    }
+
+    ///ascending order of the column
+    ///order for sorting, if tree_totals is set metrics include all descendants
+    pub fn compare(&self, a: &MyProcess, b: &MyProcess, tree_totals: bool) -> Ordering {
+        match self {
+            Column::Command => a
+                .command_display
+                .to_lowercase()
+                .cmp(&b.command_display.to_lowercase()),
+            Column::Name => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
+            Column::Pid => a.pid.cmp(&b.pid),
+            Column::User => a.user.to_lowercase().cmp(&b.user.to_lowercase()),
+            Column::Memory => a.memory.cmp(&b.memory),
+            Column::Children => a.children_memory.cmp(&b.children_memory),
+            Column::Total => a.total().cmp(&b.total()),
+            Column::Metric(metric) => metric_value(a, *metric, tree_totals)
+                .total_cmp(&metric_value(b, *metric, tree_totals)),
+            Column::MetricChildren(metric) => a.children(*metric).total_cmp(&b.children(*metric)),
+            Column::MetricTotal(metric) => {
+                a.total_usage(*metric).total_cmp(&b.total_usage(*metric))
+            }
+            Column::Threads => a.threads.cmp(&b.threads),
+            Column::State => a.state.cmp(&b.state),
+        }
+    }
+}
+
+fn metric_value(proc: &MyProcess, metric: Metric, tree_totals: bool) -> f64 {
+    if tree_totals {
+        proc.total_usage(metric)
+    } else {
+        proc.usage(metric)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ids_roundtrip() {
+        for column in Column::ALL {
+            assert_eq!(Column::from_id(&column.id()), Some(column));
+        }
+        assert_eq!(
+            Column::from_ids(&["pid".to_string(), "nope".to_string(), "cpu".to_string()]),
+            vec![Column::Pid, Column::Metric(Metric::Cpu)]
+        );
+        assert_eq!(
+            Column::from_id("diskreadtotal"),
+            Some(Column::MetricTotal(Metric::DiskRead))
+        );
+        assert_eq!(Column::MetricChildren(Metric::Cpu).header(), "CPU Ch");
+    }
+}
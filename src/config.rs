@@ -1,52 +1,182 @@
-use std::path::PathBuf;
+use std::{collections::BTreeMap, path::PathBuf, sync::OnceLock};
 
 use serde::{Deserialize, Serialize};
+use toprs::{Column, SortType, View};
 
-use crate::sorted::SortType;
+use crate::{
+    keymap::{Action, Key, Keymap},
+    theme::Theme,
+};
 
-#[derive(Serialize, Deserialize, Clone, Copy)]
+///set by --config, otherwise the file in the home config directory
+static PATH: OnceLock<PathBuf> = OnceLock::new();
+
+#[derive(Serialize, Deserialize, Clone, Copy, Default)]
 pub enum Mode {
     Gui,
+    #[default]
     Tui,
 }
 
+///Missing fields use the default, so partial configs work
 #[derive(Serialize, Deserialize)]
+#[serde(default)]
 pub struct TuiConfig {
-    pub sort_column: usize,
+    #[serde(deserialize_with = "Column::deserialize_id")]
+    pub sort_column: String,
+    pub columns: Vec<String>,
     pub sort_type: SortType,
+    pub tree_mode: bool, //keep the tree when sorting by another column
     pub show_cpu_per_core: bool,
     pub update_interval: f32,
+    pub show_disks: bool,
+    pub show_tree_totals: bool,
+    pub show_kernel_threads: bool,
+    pub filter_ancestors: bool,
+    pub auto_collapse: bool,
+    pub auto_collapse_below_mb: u64, //subtrees with less memory are grouped
+}
+
+impl Default for TuiConfig {
+    fn default() -> Self {
+        TuiConfig {
+            sort_column: Column::Command.id(),
+            columns: default_columns(),
+            sort_type: SortType::None,
+            tree_mode: false,
+            show_cpu_per_core: true,
+            update_interval: 2.0,
+            show_disks: false,
+            show_tree_totals: false,
+            show_kernel_threads: false,
+            filter_ancestors: false,
+            auto_collapse: false,
+            auto_collapse_below_mb: 50,
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct GuiConfig {
+    pub update_interval: f32,
+    pub width: u32,
+    pub height: u32,
+    pub columns: Vec<String>,
+}
+
+impl Default for GuiConfig {
+    fn default() -> Self {
+        GuiConfig {
+            update_interval: 2.0,
+            width: 1500,
+            height: 1000,
+            //up to cpu, the rest can be chosen with views
+            columns: Column::DEFAULT[..7].iter().map(|c| c.id()).collect(),
+        }
+    }
+}
+
+///Keys of the tui, changes replace the preset keys of that action
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct KeysConfig {
+    pub preset: String, //default, vi or emacs
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub bindings: BTreeMap<Action, Vec<Key>>,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
 pub struct Config {
     pub mode: Mode,
+    pub theme: String, //built in or one of themes, empty for default
     pub tui: TuiConfig,
+    pub gui: GuiConfig,
+    pub keys: KeysConfig,
+    pub views: Vec<View>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub themes: BTreeMap<String, Theme>, //user defined, missing colors use the default theme
+    #[serde(skip)]
+    pub invalid: bool, //file could not be parsed, so we do not overwrite it
+    #[serde(skip)]
+    pub warnings: Vec<String>, //unknown keys and theme, the rest of the file is used
 }
 
 impl Config {
+    ///defaults if the file is missing or invalid
     pub fn load() -> Config {
-        let path = get_home_config();
-        if path.exists()
-            && let Ok(contents) = std::fs::read_to_string(path)
-            && let Ok(config) = toml::from_str::<Config>(&contents)
-        {
-            return config;
+        Config::try_load().unwrap_or_else(|_| Config {
+            invalid: true,
+            ..Default::default()
+        })
+    }
+
+    ///missing file gives defaults, parse errors include the line number
+    pub fn try_load() -> Result<Config, String> {
+        let path = config_path();
+        if !path.exists() {
+            return Ok(Config::default());
         }
+        let contents =
+            std::fs::read_to_string(&path).map_err(|err| format!("{}: {err}", path.display()))?;
+        let mut config = toml::from_str::<Config>(&contents)
+            .map_err(|err| format!("{}: {err}", path.display()))?;
+        config.warnings = config
+            .check(&contents)
+            .into_iter()
+            .map(|warning| format!("{}: {warning}", path.display()))
+            .collect();
+        Ok(config)
+    }
 
-        //default
-        Config {
-            mode: Mode::Tui,
-            tui: TuiConfig {
-                sort_column: 0,
-                sort_type: SortType::None,
-                show_cpu_per_core: true,
-                update_interval: 2.0,
-            },
+    ///misspelled keys are ignored by serde, so they are found by saving the config again
+    fn check(&self, contents: &str) -> Vec<String> {
+        let mut unknown = vec![];
+        if let (Ok(file), Ok(known)) = (contents.parse(), toml::Table::try_from(self)) {
+            unknown_keys(&file, &known, "", &mut unknown);
         }
+        let mut warnings: Vec<String> = unknown
+            .into_iter()
+            .map(|key| format!("unknown key {key} is ignored"))
+            .collect();
+        if !self.theme.is_empty()
+            && !self.themes.contains_key(&self.theme)
+            && Theme::named(&self.theme).is_none()
+        {
+            warnings.push(format!("unknown theme {}, using the default", self.theme));
+        }
+        warnings
+    }
+
+    ///override the config file location, must be called before loading
+    pub fn set_path(path: PathBuf) {
+        let _ = PATH.set(path);
+    }
+
+    ///user themes take precedence over built in ones with the same name
+    ///an unknown name gives the default theme, it is reported in warnings when loading
+    pub fn theme(&self) -> Theme {
+        self.themes
+            .get(&self.theme)
+            .cloned()
+            .or_else(|| Theme::named(&self.theme))
+            .unwrap_or_default()
     }
+
+    pub fn keymap(&self) -> Keymap {
+        Keymap::new(&self.keys.preset, &self.keys.bindings)
+    }
+
+    pub fn to_toml(&self) -> String {
+        toml::to_string(self).unwrap_or_default()
+    }
+
     pub fn save(&self) {
-        let path = get_home_config();
+        if self.invalid {
+            return;
+        }
+        let path = config_path();
         if let Ok(toml_str) = toml::to_string(self) {
             if let Some(parent) = path.parent() {
                 let _ = std::fs::create_dir_all(parent);
@@ -56,6 +186,14 @@ impl Config {
     }
 }
 
+fn default_columns() -> Vec<String> {
+    Column::DEFAULT.iter().map(|c| c.id()).collect()
+}
+
+fn config_path() -> PathBuf {
+    PATH.get().cloned().unwrap_or_else(get_home_config)
+}
+
 fn get_home_config() -> PathBuf {
     //home directory
     if let Some(mut dir) = dirs::config_dir() {
@@ -66,3 +204,82 @@ fn get_home_config() -> PathBuf {
     //should not happen, but just in case
     PathBuf::from("config.toml")
 }
+
+///keys of file which are missing in known, as dotted paths
+fn unknown_keys(file: &toml::Table, known: &toml::Table, prefix: &str, found: &mut Vec<String>) {
+    use toml::Value;
+    for (key, value) in file {
+        let path = format!("{prefix}{key}");
+        match (value, known.get(key)) {
+            //empty maps are not saved
+            (Value::Table(table), None) if table.is_empty() => {}
+            (_, None) => found.push(path),
+            (Value::Table(table), Some(Value::Table(known))) => {
+                unknown_keys(table, known, &format!("{path}."), found)
+            }
+            (Value::Array(items), Some(Value::Array(known))) => {
+                for (i, (item, known)) in items.iter().zip(known).enumerate() {
+                    if let (Value::Table(table), Value::Table(known)) = (item, known) {
+                        unknown_keys(table, known, &format!("{path}[{i}]."), found);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use toprs::ProcState;
+
+    #[test]
+    fn test_partial_config() {
+        let config: Config = toml::from_str("mode = \"Gui\"\n[tui]\nsort_column = 3\n").unwrap();
+        assert!(matches!(config.mode, Mode::Gui));
+        assert_eq!(config.tui.sort_column, "mem");
+        let config: Config = toml::from_str("[tui]\nsort_column = 7\n").unwrap();
+        assert_eq!(config.tui.sort_column, "diskread");
+        assert_eq!(config.tui.update_interval, 2.0);
+        assert_eq!(config.tui.columns, default_columns());
+        assert_eq!(config.gui.width, 1500);
+
+        let config: Config = toml::from_str(
+            "[keys]\npreset = \"vi\"\n[keys.bindings]\nkill = [\"x\", \"ctrl-k\"]\n",
+        )
+        .unwrap();
+        assert_eq!(config.keys.bindings[&Action::Kill].len(), 2);
+        assert!(toml::from_str::<Config>("[keys.bindings]\nkill = [\"ctrl-nope\"]\n").is_err());
+        assert!(config.to_toml().contains("kill = [\"x\", \"ctrl-k\"]"));
+
+        let err = toml::from_str::<Config>("mode = \"Gui\"\n[tui]\nupdate_interval = \"x\"\n")
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("line 3"));
+
+        //views saved before the display settings keep the current ones
+        let config: Config = toml::from_str(
+            "[[views]]\nname = \"old\"\n[[views]]\nname = \"z\"\nhidezeros = false\nstate_filter = \"Zombie\"\nauto_collapse = 1024\n",
+        )
+        .unwrap();
+        assert_eq!(config.views[0].hidezeros, None);
+        assert_eq!(config.views[1].hidezeros, Some(false));
+        assert_eq!(config.views[1].state_filter, Some(ProcState::Zombie));
+        assert_eq!(config.views[1].auto_collapse, Some(1024));
+        assert!(config.to_toml().contains("state_filter = \"Zombie\""));
+
+        let contents = "theme = \"nope\"\n[tui]\nupdate_intervall = 1\n[keys.bindings]\n[[views]]\nname = \"a\"\nfiltr = \"x\"\n";
+        let config: Config = toml::from_str(contents).unwrap();
+        assert_eq!(
+            config.check(contents),
+            vec![
+                "unknown key tui.update_intervall is ignored",
+                "unknown key views[0].filtr is ignored",
+                "unknown theme nope, using the default",
+            ]
+        );
+        let config = Config::default();
+        assert!(config.check(&config.to_toml()).is_empty());
+    }
+}
@@ -0,0 +1,158 @@
+use std::{collections::HashMap, fs};
+
+use sysinfo::{Groups, Process, Users};
+
+///Detailed information of a single process, loaded on demand for the info view
+#[non_exhaustive]
+pub struct ProcessDetails {
+    ///process id
+    pub pid: usize,
+    ///command line arguments, the first is the program
+    pub argv: Vec<String>,
+    ///path of the executable
+    pub exe: String,
+    ///working directory
+    pub cwd: String,
+    ///environment as KEY=value
+    pub environ: Vec<String>,
+    ///user name, or id if unknown
+    pub user: String,
+    ///group name, or id if unknown
+    pub group: String,
+    ///scheduling state as reported by the system
+    pub state: String,
+    ///number of threads, if known
+    pub threads: Option<usize>,
+    ///seconds since epoch
+    pub start_time: u64,
+    ///number of open file descriptors, linux only
+    pub open_files: Option<usize>,
+    ///soft limit of open files, linux only
+    pub open_files_limit: Option<usize>,
+    ///memory by kind
+    pub memory: MemoryBreakdown,
+    ///resource limits as reported by the system, linux only
+    pub limits: Vec<String>,
+}
+
+///Memory usage in bytes, fields not available on this platform are None
+#[derive(Default)]
+#[non_exhaustive]
+pub struct MemoryBreakdown {
+    ///resident memory
+    pub rss: u64,
+    ///virtual memory
+    pub virt: u64,
+    ///resident memory shared with other processes
+    pub shared: Option<u64>,
+    ///code
+    pub text: Option<u64>,
+    ///data and stack
+    pub data: Option<u64>,
+    ///swapped out
+    pub swap: Option<u64>,
+}
+
+pub fn load(pid: usize, process: &Process, users: &Users, groups: &Groups) -> ProcessDetails {
+    let status = read_status(pid);
+    let kb = |key: &str| status.get(key).and_then(|v| parse_kb(v));
+
+    let user = process
+        .user_id()
+        .map(|uid| match users.get_user_by_id(uid) {
+            Some(user) => format!("{} ({})", user.name(), **uid),
+            None => (**uid).to_string(),
+        })
+        .unwrap_or_default();
+    let group = process
+        .group_id()
+        .map(|gid| match groups.list().iter().find(|g| g.id() == &gid) {
+            Some(group) => format!("{} ({})", group.name(), *gid),
+            None => (*gid).to_string(),
+        })
+        .unwrap_or_default();
+
+    ProcessDetails {
+        pid,
+        argv: to_strings(process.cmd()),
+        exe: process
+            .exe()
+            .map(|p| p.to_string_lossy().to_string())
+            .unwrap_or_default(),
+        cwd: process
+            .cwd()
+            .map(|p| p.to_string_lossy().to_string())
+            .unwrap_or_default(),
+        environ: to_strings(process.environ()),
+        user,
+        group,
+        state: process.status().to_string(),
+        threads: status
+            .get("Threads")
+            .and_then(|t| t.parse().ok())
+            .or_else(|| process.tasks().map(|t| t.len())),
+        start_time: process.start_time(),
+        open_files: process.open_files(),
+        open_files_limit: process.open_files_limit(),
+        memory: MemoryBreakdown {
+            rss: process.memory(),
+            virt: process.virtual_memory(),
+            shared: kb("RssFile")
+                .zip(kb("RssShmem"))
+                .map(|(file, shmem)| file + shmem),
+            text: kb("VmExe"),
+            data: kb("VmData"),
+            swap: kb("VmSwap"),
+        },
+        limits: read_limits(pid),
+    }
+}
+
+fn to_strings(vals: &[std::ffi::OsString]) -> Vec<String> {
+    vals.iter()
+        .map(|os| os.to_string_lossy().to_string())
+        .collect()
+}
+
+///key value pairs of /proc/<pid>/status (linux only, empty otherwise)
+fn read_status(pid: usize) -> HashMap<String, String> {
+    fs::read_to_string(format!("/proc/{pid}/status"))
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|line| line.split_once(':'))
+        .map(|(k, v)| (k.to_string(), v.trim().to_string()))
+        .collect()
+}
+
+///"1234 kB" -> 1234*1024
+fn parse_kb(val: &str) -> Option<u64> {
+    val.trim_end_matches("kB")
+        .trim()
+        .parse::<u64>()
+        .ok()
+        .map(|v| v * 1024)
+}
+
+///lines of /proc/<pid>/limits (linux only, empty otherwise)
+fn read_limits(pid: usize) -> Vec<String> {
+    fs::read_to_string(format!("/proc/{pid}/limits"))
+        .unwrap_or_default()
+        .lines()
+        .map(|l| l.trim_end().to_string())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_kb() {
+        assert_eq!(parse_kb("1234 kB"), Some(1234 * 1024));
+        assert_eq!(parse_kb("0 kB"), Some(0));
+        assert_eq!(parse_kb("12"), Some(12 * 1024));
+        assert_eq!(parse_kb(""), None);
+        assert_eq!(parse_kb("n/a kB"), None);
+        assert_eq!(parse_kb("-5 kB"), None);
+    }
+}
@@ -3,12 +3,14 @@ use std::{cmp::Reverse, time::Duration};
 use dioxus::{document::eval, prelude::*};
 use dioxus_desktop::{Config, WindowBuilder, tao::window::Icon, wry::dpi::PhysicalSize};
 
-use crate::{
+use toprs::{
+    Column, MyProcess, ProcManager, SortType, SortedProcesses, generate_svg,
     helpers::{nice_size_g_thousands, nice_size_thousands, nice_time},
-    manager::ProcManager,
-    myprocess::MyProcess,
-    sorted::{SortType, SortedProcesses},
-    svgmaker,
+};
+
+use crate::{
+    config,
+    theme::{Theme, css},
 };
 
 ///Hide console window because we are running gui
@@ -30,11 +32,12 @@ pub fn run() {
 
     let index = include_str!("index.html").to_string();
     let index = index.replace("PLOTLYSCRIPT", include_str!("plotly-2.18.0.min.js"));
+    let gui = config::Config::load().gui;
 
     let config = Config::default().with_custom_index(index).with_window(
         WindowBuilder::new()
             .with_title("toprs")
-            .with_inner_size(PhysicalSize::new(1500, 1000))
+            .with_inner_size(PhysicalSize::new(gui.width, gui.height))
             .with_window_icon(Some(load_icon())),
     );
     dioxus_desktop::launch::launch(app, vec![], vec![Box::new(config)]);
@@ -57,22 +60,40 @@ fn app() -> Element {
     );
     let uptime = nice_time(totals.uptime);
     let mut live = use_signal(|| true);
-    let mut visible = use_signal(SortedProcesses::new);
+    let config = use_signal(config::Config::load);
+    let mut visible = use_signal(|| {
+        let mut visible = SortedProcesses::new();
+        let columns = Column::from_ids(&config.read().gui.columns);
+        if !columns.is_empty() {
+            visible.columns = columns;
+        }
+        visible
+    });
+    let columns = visible.read().columns.clone();
     let processes = use_signal(|| man.read().procs().len());
+    let tree_totals = visible.read().tree_totals;
+    let tree_mode = visible.read().tree_mode;
+    let filter = visible.read().filter.clone();
 
     use_coroutine(move |_: UnboundedReceiver<()>| async move {
         loop {
             if *live.read() {
-                man.with_mut(|s| s.update());
+                let metrics = visible.read().metrics();
+                man.with_mut(|s| {
+                    s.set_tracked(&metrics);
+                    s.update()
+                });
                 let procs = man.read();
                 let procs = procs.procs();
-                my_svg.set(svgmaker::generate_svg(procs));
+                let theme = config.read().theme();
+                my_svg.set(generate_svg(procs, &theme.svg_colors()));
                 visible.write().update(procs);
 
                 //update starburst data
-                update_sunburst(procs, max_depth);
+                update_sunburst(procs, max_depth, &theme);
             }
-            tokio::time::sleep(Duration::from_millis(2000)).await;
+            let interval = config.read().gui.update_interval.max(0.5);
+            tokio::time::sleep(Duration::from_secs_f32(interval)).await;
         }
     });
 
@@ -101,49 +122,94 @@ fn app() -> Element {
                 td { class: "tot", "{processes}" }
             }
         }
+        div {
+            "View"
+            select {
+                style: "margin-left:20px",
+                onchange: move |a| {
+                    let name = a.value();
+                    let view = config
+                        .read()
+                        .views
+                        .iter()
+                        .find(|v| v.name == name)
+                        .cloned()
+                        .unwrap_or_default();
+                    visible.write().apply_view(&view);
+                    visible.write().update(man.read().procs());
+                },
+                option { value: "", "Default" }
+                for view in config.read().views.iter() {
+                    option { value: "{view.name}", "{view.name}" }
+                }
+            }
+        }
         div {
             "Filter"
             input {
                 style: "margin-left:20px",
+                value: "{filter}",
                 oninput: move |a| {
                     visible.write().set_filter(a.value().clone());
                 }
             }
         }
+        div {
+            "Include children"
+            input {
+                style: "margin-left:20px",
+                r#type: "checkbox",
+                checked: "{tree_totals}",
+                oninput: move |_| {
+                    let old = visible.read().tree_totals;
+                    visible.write().tree_totals = !old;
+                    visible.write().update(man.read().procs());
+                }
+            }
+        }
+        div {
+            "Keep tree when sorting"
+            input {
+                style: "margin-left:20px",
+                r#type: "checkbox",
+                checked: "{tree_mode}",
+                oninput: move |_| {
+                    let old = visible.read().tree_mode;
+                    visible.write().tree_mode = !old;
+                    visible.write().update(man.read().procs());
+                }
+            }
+        }
         div { style: "height:300px;overflow:auto",
             table { class: "tproc",
                 thead {
                     tr { class: "thead",
-                        for (i , p) in ["Command", "Name", "PID", "Self", "Children", "Total", "CPU"].iter().enumerate() {
+                        for col in columns.iter().copied() {
                             td {
                             onclick: move |_| {
-                                    if visible.read().sort_col == i {
+                                    if visible.read().sort_col == col {
                                         visible.write().sort_cycle();
                                     } else {
-                                        visible.write().sort_col = i;
+                                        visible.write().sort_col = col;
                                     }
-                                    if visible.read().sort_type == SortType::None && i > 0 {
+                                    if visible.read().sort_type == SortType::None && col != Column::Command {
                                         visible.write().sort_cycle();
                                     }
                                     visible.write().update(man.read().procs());
                                 },
-                                style: if i == 0 { "width:700px" } else{"width:90px" },
-                                class: if i < 2 { "" } else { "tright" },
-                                "{sort_name(p,i,visible)}"
+                                style: if col == Column::Command { "width:700px" } else{"width:90px" },
+                                class: if col.right_align() { "tright" } else { "" },
+                                "{sort_name(col,visible)}"
                             }
                         }
                     }
                 }
                 tbody {
-                    for pr in visible.read().procs().iter() {
+                    for row in visible.read().rows().iter() {
                         tr {
-                            td{title:"{pr[0]}",class:"tcell ","{pr[0]}"}
-                            td{title:"{pr[1]}",class:"tcell ","{pr[1]}"}
-                            td{class:"tcell tright","{pr[2]}"}
-                            td{class:"tcell tright","{pr[3]}"}
-                            td{class:"tcell tright","{pr[4]}"}
-                            td{class:"tcell tright","{pr[5]}"}
-                            td{class:"tcell tright","{pr[6]}"}
+                            for (val , col) in columns.iter().map(|c| (visible.read().format(row, *c), c)) {
+                                td{title:"{val}",class: if col.right_align() { "tcell tright" } else { "tcell " },"{val}"}
+                            }
                         }
                     }
                 }
@@ -180,8 +246,8 @@ fn app() -> Element {
     }
 }
 
-fn update_sunburst(procs: &[MyProcess], max: Signal<usize>) {
-    let (l, p, v, t, m, c) = get_labels_parents_values(procs);
+fn update_sunburst(procs: &[MyProcess], max: Signal<usize>, theme: &Theme) {
+    let (l, p, v, t, m, c) = get_labels_parents_values(procs, theme);
     let js = r##"
 
      var data = [{
@@ -194,7 +260,7 @@ fn update_sunburst(procs: &[MyProcess], max: Signal<usize>) {
          text:[TEXT],
          //hoverinfo: "label+value+percent root",
          hovertemplate:"%{text}  %{percentRoot:.1%} with children %{meta} of total <extra></extra>",
-         outsidetextfont: {size: 20, color: "#377eb8"},
+         outsidetextfont: {size: 20, color: "TEXTCOLOR"},
          leaf: {opacity: 1.0},
          marker: {line: {width: 2},colors:[COLORS]},
          branchvalues:"total",
@@ -227,6 +293,7 @@ fn update_sunburst(procs: &[MyProcess], max: Signal<usize>) {
         .replace("TEXT", &t)
         .replace("META", &m)
         .replace("COLORS", &c)
+        .replace("TEXTCOLOR", &css(theme.chart_text))
         .replace("MAXDEPTHVALUE", &max.read().to_string());
     let _ = eval(&js);
 }
@@ -237,6 +304,7 @@ fn proc_label(proc: &MyProcess) -> String {
 
 fn get_labels_parents_values(
     procs: &[MyProcess],
+    theme: &Theme,
 ) -> (String, String, String, String, String, String) {
     let labels = procs
         .iter()
@@ -278,16 +346,13 @@ fn get_labels_parents_values(
     //let fifth_largest: u64 = get_top5(procs).last().unwrap().memory;
     let top = get_top_memory(procs).memory;
 
+    let svg_colors = theme.svg_colors();
     let colors = procs
         .iter()
         .map(|t| {
-            //color from red to light yellow based on memory usage compared to top
+            //color along the theme gradient based on memory usage compared to top
             let ratio = (t.memory as f32 / top as f32).powf(0.3);
-            let g = ((1.0 - ratio) * 255.0) as u32;
-            let b = ((1.0 - ratio) * 150.0) as u32;
-            let col = format!("rgb(255, {g}, {b})");
-
-            format!(r#""{}""#, col)
+            format!(r#""{}""#, svg_colors.memory(ratio))
         })
         .collect::<Vec<String>>()
         .join(",");
@@ -311,7 +376,8 @@ fn get_top_memory(procs: &[MyProcess]) -> &MyProcess {
     procs.iter().max_by_key(|a| a.memory).unwrap()
 }
 
-fn sort_name(name: &str, col: usize, sorted: Signal<SortedProcesses>) -> String {
+fn sort_name(col: Column, sorted: Signal<SortedProcesses>) -> String {
+    let name = col.header();
     let sym = if col == sorted.read().sort_col {
         match sorted.read().sort_type {
             SortType::Ascending => "↑",
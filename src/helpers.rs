@@ -6,6 +6,7 @@ pub fn nice_size(val: u64) -> String {
     nice_size_ops(val, false, true)
 }
 
+///like nice_size, with thousand separators
 #[cfg(feature = "gui")]
 pub fn nice_size_thousands(val: u64) -> String {
     nice_size_ops(val, true, true)
@@ -33,15 +34,24 @@ pub fn nice_size_ops(val: u64, include_thousands: bool, zero_blank: bool) -> Str
     }
 }
 
+///always in G
 pub fn nice_size_g(val: u64) -> String {
     format!("{:.1}G", val as f64 / 1024. / 1024. / 1024.)
 }
 
+///always in G, with thousand separators
 #[cfg(feature = "gui")]
 pub fn nice_size_g_thousands(val: u64) -> String {
     (val as f64 / 1024. / 1024. / 1024.).formato("#,###.0G")
 }
 
+///cpu time as H:MM:SS, hours are not wrapped into days
+pub fn nice_cpu_time(secs: f64) -> String {
+    let secs = secs as u64;
+    format!("{}:{:02}:{:02}", secs / 3600, secs % 3600 / 60, secs % 60)
+}
+
+///duration split into days, hours, minutes and seconds, like "05m 03s"
 pub fn nice_time(time_seconds: u64) -> String {
     let mut secs = time_seconds;
     if secs < 60 {
@@ -69,3 +79,49 @@ pub fn nice_time(time_seconds: u64) -> String {
         format!("{d:>2}d {h:>2}h {m:>2}m {s:>2}s ")
     }
 }
+
+///color between low and high as red, green and blue, val from 0 to 1
+///channels are truncated like the original fixed colors
+pub fn mix_rgb(low: (u8, u8, u8), high: (u8, u8, u8), val: f32) -> (u8, u8, u8) {
+    let val = val.clamp(0., 1.);
+    let mix = |a: u8, b: u8| match a == b {
+        true => a,
+        false => (a as f32 * (1. - val) + b as f32 * val) as u8,
+    };
+    (mix(low.0, high.0), mix(low.1, high.1), mix(low.2, high.2))
+}
+
+///seconds since epoch to "YYYY-MM-DD HH:MM:SS UTC"
+pub fn nice_timestamp(secs: u64) -> String {
+    let days = (secs / 86400) as i64;
+    let rem = secs % 86400;
+    //convert days since epoch to civil date (Howard Hinnant's algorithm)
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!(
+        "{year:04}-{month:02}-{day:02} {:02}:{:02}:{:02} UTC",
+        rem / 3600,
+        rem % 3600 / 60,
+        rem % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nice_timestamp() {
+        assert_eq!(nice_timestamp(0), "1970-01-01 00:00:00 UTC");
+        assert_eq!(nice_timestamp(951782400), "2000-02-29 00:00:00 UTC");
+        assert_eq!(nice_timestamp(1700000000), "2023-11-14 22:13:20 UTC");
+        assert_eq!(nice_timestamp(4102444799), "2099-12-31 23:59:59 UTC");
+    }
+}
@@ -0,0 +1,460 @@
+use std::{collections::BTreeMap, fmt, str::FromStr};
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::{Deserialize, Serialize};
+
+///Action of the process table which can be bound to keys, stored in config by id
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    Up,
+    Down,
+    PageUp,
+    PageDown,
+    First,
+    Last,
+    SortLeft,
+    SortRight,
+    SortCycle,
+    Tree,
+    TreeMode,
+    Quit,
+    Help,
+    Info,
+    OpenFiles,
+    Ancestry,
+    Filter,
+    Search,
+    Follow,
+    Collapse,
+    CollapseAll,
+    ExpandAll,
+    CollapseLevel,
+    ExpandLevel,
+    ExpandToSelected,
+    AutoCollapse,
+    HideZeros,
+    TreeTotals,
+    Ancestors,
+    Family,
+    KernelThreads,
+    StateFilter,
+    Columns,
+    SaveView,
+    ToggleCores,
+    ToggleDisks,
+    Kill,
+    Stop,
+    StopTree,
+    Continue,
+    ContinueTree,
+    Affinity,
+    MemoryMap,
+    Threads,
+    NextTheme,
+    Slower,
+    Faster,
+    Gui,
+}
+
+impl Action {
+    ///in the order shown in help
+    pub const ALL: [Action; 48] = [
+        Action::Up,
+        Action::Down,
+        Action::PageUp,
+        Action::PageDown,
+        Action::First,
+        Action::Last,
+        Action::SortLeft,
+        Action::SortRight,
+        Action::SortCycle,
+        Action::Tree,
+        Action::TreeMode,
+        Action::Quit,
+        Action::Help,
+        Action::Info,
+        Action::OpenFiles,
+        Action::Ancestry,
+        Action::Filter,
+        Action::Search,
+        Action::Follow,
+        Action::Collapse,
+        Action::CollapseAll,
+        Action::ExpandAll,
+        Action::CollapseLevel,
+        Action::ExpandLevel,
+        Action::ExpandToSelected,
+        Action::AutoCollapse,
+        Action::HideZeros,
+        Action::TreeTotals,
+        Action::Ancestors,
+        Action::Family,
+        Action::KernelThreads,
+        Action::StateFilter,
+        Action::Columns,
+        Action::SaveView,
+        Action::ToggleCores,
+        Action::ToggleDisks,
+        Action::Kill,
+        Action::Stop,
+        Action::StopTree,
+        Action::Continue,
+        Action::ContinueTree,
+        Action::Affinity,
+        Action::MemoryMap,
+        Action::Threads,
+        Action::NextTheme,
+        Action::Slower,
+        Action::Faster,
+        Action::Gui,
+    ];
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            Action::Up => "Move up",
+            Action::Down => "Move down",
+            Action::PageUp => "Page up",
+            Action::PageDown => "Page down",
+            Action::First => "Go to first row",
+            Action::Last => "Go to last row",
+            Action::SortLeft => "Sort by previous column",
+            Action::SortRight => "Sort by next column",
+            Action::SortCycle => "Sort Asc/Desc/None",
+            Action::Tree => "Tree view",
+            Action::TreeMode => "Keep tree when sorting",
+            Action::Quit => "Exit",
+            Action::Help => "Show/hide help",
+            Action::Info => "Process details",
+            Action::OpenFiles => "Open files of process",
+            Action::Ancestry => "Ancestors of process",
+            Action::Filter => "Filter, e.g. mem>500M user:root",
+            Action::Search => "Search for process",
+            Action::Follow => "Follow process",
+            Action::Collapse => "Collapse/expand children",
+            Action::CollapseAll => "Collapse all",
+            Action::ExpandAll => "Expand all",
+            Action::CollapseLevel => "Collapse deepest level",
+            Action::ExpandLevel => "Expand one more level",
+            Action::ExpandToSelected => "Collapse all but the path to selected",
+            Action::AutoCollapse => "Group small processes in tree",
+            Action::HideZeros => "Hide/show zero memory",
+            Action::TreeTotals => "CPU/disk/net including children",
+            Action::Ancestors => "Show ancestors of filtered processes",
+            Action::Family => "Only show process, ancestors and descendants",
+            Action::KernelThreads => "Show/hide kernel threads",
+            Action::StateFilter => "Filter by process state",
+            Action::Columns => "Choose and reorder columns",
+            Action::SaveView => "Save filter, sort and collapsed as view",
+            Action::ToggleCores => "Hide CPU cores",
+            Action::ToggleDisks => "Show/hide disks",
+            Action::Kill => "Send signal to process",
+            Action::Stop => "Stop process",
+            Action::StopTree => "Stop process tree",
+            Action::Continue => "Continue process",
+            Action::ContinueTree => "Continue process tree",
+            Action::Affinity => "Edit CPU affinity",
+            Action::MemoryMap => "Memory map of process",
+            Action::Threads => "Threads of process",
+            Action::NextTheme => "Next color theme",
+            Action::Slower => "Increase update interval",
+            Action::Faster => "Decrease update interval",
+            Action::Gui => "Start GUI mode",
+        }
+    }
+
+    fn default_keys(&self) -> &'static [&'static str] {
+        match self {
+            Action::Up => &["up", "k"],
+            Action::Down => &["down", "j"],
+            Action::PageUp => &["pageup"],
+            Action::PageDown => &["pagedown"],
+            Action::First => &["home"],
+            Action::Last => &["end"],
+            Action::SortLeft => &["left", "h"],
+            Action::SortRight => &["right", "l"],
+            Action::SortCycle => &["s"],
+            Action::Tree => &["t"],
+            Action::TreeMode => &["w"],
+            Action::Quit => &["q", "ctrl-c"],
+            Action::Help => &["?", "f1"],
+            Action::Info => &["enter"],
+            Action::OpenFiles => &["o"],
+            Action::Ancestry => &["i"],
+            Action::Filter => &["f"],
+            Action::Search => &["/"],
+            Action::Follow => &["F"],
+            Action::Collapse => &["m"],
+            Action::CollapseAll => &["<"],
+            Action::ExpandAll => &[">"],
+            Action::CollapseLevel => &["["],
+            Action::ExpandLevel => &["]"],
+            Action::ExpandToSelected => &["e"],
+            Action::AutoCollapse => &["u"],
+            Action::HideZeros => &["z"],
+            Action::TreeTotals => &["T"],
+            Action::Ancestors => &["A"],
+            Action::Family => &["I"],
+            Action::KernelThreads => &["K"],
+            Action::StateFilter => &["S"],
+            Action::Columns => &["C"],
+            Action::SaveView => &["V"],
+            Action::ToggleCores => &["c"],
+            Action::ToggleDisks => &["d"],
+            Action::Kill => &["ctrl-k"],
+            Action::Stop => &["p"],
+            Action::StopTree => &["P"],
+            Action::Continue => &["r"],
+            Action::ContinueTree => &["R"],
+            Action::Affinity => &["a"],
+            Action::MemoryMap => &["M"],
+            Action::Threads => &["H"],
+            Action::NextTheme => &["Y"],
+            Action::Slower => &["+"],
+            Action::Faster => &["-"],
+            Action::Gui => &["g"],
+        }
+    }
+}
+
+type Bindings = &'static [(Action, &'static [&'static str])];
+
+///changes to the default keys
+const VI: Bindings = &[
+    (Action::PageUp, &["pageup", "ctrl-u", "ctrl-b"]),
+    (Action::PageDown, &["pagedown", "ctrl-d", "ctrl-f"]),
+    (Action::First, &["home", "g"]),
+    (Action::Last, &["end", "G"]),
+    (Action::Gui, &["ctrl-g"]),
+];
+
+const EMACS: Bindings = &[
+    (Action::Up, &["up", "ctrl-p"]),
+    (Action::Down, &["down", "ctrl-n"]),
+    (Action::PageUp, &["pageup", "alt-v"]),
+    (Action::PageDown, &["pagedown", "ctrl-v"]),
+    (Action::First, &["home", "alt-<"]),
+    (Action::Last, &["end", "alt->"]),
+    (Action::SortLeft, &["left", "ctrl-b"]),
+    (Action::SortRight, &["right", "ctrl-f"]),
+    (Action::Search, &["/", "ctrl-s"]),
+    (Action::Quit, &["q", "ctrl-c", "ctrl-x"]),
+];
+
+///A key with modifiers, stored in config like "ctrl-k", "pagedown" or "P"
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Key {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+
+impl Key {
+    ///shift is part of the character, so only checked for other keys
+    pub fn matches(&self, event: &KeyEvent) -> bool {
+        let (mine, theirs) = match self.code {
+            KeyCode::Char(_) => (
+                self.modifiers - KeyModifiers::SHIFT,
+                event.modifiers - KeyModifiers::SHIFT,
+            ),
+            _ => (self.modifiers, event.modifiers),
+        };
+        self.code == event.code && mine == theirs
+    }
+}
+
+const NAMED: [(&str, KeyCode); 14] = [
+    ("up", KeyCode::Up),
+    ("down", KeyCode::Down),
+    ("left", KeyCode::Left),
+    ("right", KeyCode::Right),
+    ("pageup", KeyCode::PageUp),
+    ("pagedown", KeyCode::PageDown),
+    ("home", KeyCode::Home),
+    ("end", KeyCode::End),
+    ("enter", KeyCode::Enter),
+    ("esc", KeyCode::Esc),
+    ("tab", KeyCode::Tab),
+    ("backspace", KeyCode::Backspace),
+    ("delete", KeyCode::Delete),
+    ("space", KeyCode::Char(' ')),
+];
+
+impl FromStr for Key {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut modifiers = KeyModifiers::NONE;
+        let mut rest = s;
+        //a lone "-" is a key, not a modifier separator
+        while let Some((prefix, key)) = rest.split_once('-')
+            && !key.is_empty()
+        {
+            modifiers |= match prefix.to_lowercase().as_str() {
+                "ctrl" | "c" => KeyModifiers::CONTROL,
+                "alt" | "m" => KeyModifiers::ALT,
+                "shift" | "s" => KeyModifiers::SHIFT,
+                _ => return Err(format!("unknown modifier in key {s}")),
+            };
+            rest = key;
+        }
+        let mut chars = rest.chars();
+        let code = match (chars.next(), chars.next()) {
+            //terminals send shifted letters as the uppercase char
+            (Some(c), None) if modifiers.contains(KeyModifiers::SHIFT) => {
+                modifiers -= KeyModifiers::SHIFT;
+                KeyCode::Char(c.to_uppercase().next().unwrap_or(c))
+            }
+            (Some(c), None) => KeyCode::Char(c),
+            _ => {
+                let lower = rest.to_lowercase();
+                match NAMED.iter().find(|(name, _)| *name == lower) {
+                    Some((_, code)) => *code,
+                    None => match lower.strip_prefix('f').and_then(|n| n.parse().ok()) {
+                        Some(n @ 1..=12) => KeyCode::F(n),
+                        _ => return Err(format!("unknown key {s}")),
+                    },
+                }
+            }
+        };
+        Ok(Key { code, modifiers })
+    }
+}
+
+impl TryFrom<String> for Key {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<Key> for String {
+    fn from(key: Key) -> Self {
+        key.to_string()
+    }
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            write!(f, "ctrl-")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            write!(f, "alt-")?;
+        }
+        match self.code {
+            KeyCode::Char(c) if c != ' ' => write!(f, "{c}"),
+            KeyCode::F(n) => write!(f, "f{n}"),
+            code => {
+                if self.modifiers.contains(KeyModifiers::SHIFT) {
+                    write!(f, "shift-")?;
+                }
+                let name = NAMED.iter().find(|(_, c)| *c == code);
+                write!(f, "{}", name.map_or("?", |(name, _)| name))
+            }
+        }
+    }
+}
+
+///Keys of each action, from a preset with changes from the config
+pub struct Keymap {
+    bindings: Vec<(Key, Action)>,
+}
+
+impl Keymap {
+    ///unknown presets use the default keys
+    pub fn new(preset: &str, changes: &BTreeMap<Action, Vec<Key>>) -> Keymap {
+        let mut keymap = Keymap {
+            bindings: Action::ALL
+                .iter()
+                .flat_map(|action| {
+                    action
+                        .default_keys()
+                        .iter()
+                        .filter_map(|key| key.parse().ok())
+                        .map(|key| (key, *action))
+                })
+                .collect(),
+        };
+        let preset = match preset {
+            "vi" => VI,
+            "emacs" => EMACS,
+            _ => &[],
+        };
+        for (action, keys) in preset {
+            let keys: Vec<Key> = keys.iter().filter_map(|key| key.parse().ok()).collect();
+            keymap.bind(*action, &keys);
+        }
+        for (action, keys) in changes {
+            keymap.bind(*action, keys);
+        }
+        keymap
+    }
+
+    ///replaces the keys of the action, and removes them from other actions
+    fn bind(&mut self, action: Action, keys: &[Key]) {
+        self.bindings
+            .retain(|(key, a)| *a != action && !keys.contains(key));
+        self.bindings.extend(keys.iter().map(|key| (*key, action)));
+    }
+
+    pub fn action(&self, event: &KeyEvent) -> Option<Action> {
+        self.bindings
+            .iter()
+            .find(|(key, _)| key.matches(event))
+            .map(|(_, action)| *action)
+    }
+
+    pub fn keys(&self, action: Action) -> Vec<Key> {
+        self.bindings
+            .iter()
+            .filter(|(_, a)| *a == action)
+            .map(|(key, _)| *key)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keymap() {
+        for key in [
+            "ctrl-k",
+            "P",
+            "-",
+            "ctrl--",
+            "pagedown",
+            "f1",
+            "alt-<",
+            "shift-tab",
+        ] {
+            assert_eq!(key.parse::<Key>().unwrap().to_string(), key);
+        }
+        assert_eq!("shift-a".parse::<Key>().unwrap().to_string(), "A");
+        assert_eq!("ctrl-shift-a".parse::<Key>().unwrap().to_string(), "ctrl-A");
+        assert!("ctrl-nope".parse::<Key>().is_err());
+        assert!("hyper-k".parse::<Key>().is_err());
+
+        let press = |code, modifiers| KeyEvent::new(code, modifiers);
+        let changes = BTreeMap::from([(Action::Kill, vec!["k".parse().unwrap()])]);
+        let keymap = Keymap::new("vi", &changes);
+        assert_eq!(
+            keymap.action(&press(KeyCode::Char('k'), KeyModifiers::NONE)),
+            Some(Action::Kill)
+        );
+        assert_eq!(
+            keymap.action(&press(KeyCode::Char('G'), KeyModifiers::SHIFT)),
+            Some(Action::Last)
+        );
+        assert_eq!(
+            keymap.action(&press(KeyCode::Char('k'), KeyModifiers::CONTROL)),
+            None
+        );
+        assert_eq!(keymap.keys(Action::Up), vec!["up".parse().unwrap()]);
+        let shifted: Key = "shift-g".parse().unwrap();
+        assert!(shifted.matches(&press(KeyCode::Char('G'), KeyModifiers::SHIFT)));
+    }
+}
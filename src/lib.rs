@@ -0,0 +1,47 @@
+//! Memory focused process information, with memory and usage added up over the process tree.
+//!
+//! [`ProcManager`] collects the processes, [`Snapshot`] keeps them with helpers to walk the tree,
+//! [`SortedProcesses`] filters and sorts them like the table of the tui,
+//! and [`generate_svg`] draws them as a flamegraph.
+//!
+//! The tui and gui are behind the default `tui` and `gui` features,
+//! use `default-features = false` for the library alone.
+//!
+//! ```no_run
+//! use toprs::{ProcManager, helpers::nice_size};
+//!
+//! let mut manager = ProcManager::new();
+//! manager.update(); //cpu and disk usage need two samples
+//! let snapshot = manager.snapshot();
+//! for (depth, proc) in snapshot.walk().take(20) {
+//!     let indent = "  ".repeat(depth - 1); //roots have depth 1
+//!     println!("{indent}{} {}", proc.name, nice_size(proc.total()));
+//! }
+//! ```
+
+mod columns;
+mod details;
+///formatting of sizes and times as shown in the tui
+pub mod helpers;
+mod manager;
+mod mydisk;
+mod mynetwork;
+mod myprocess;
+mod mythread;
+mod procnet;
+mod procstat;
+mod query;
+mod snapshot;
+mod sorted;
+mod svgmaker;
+
+pub use columns::Column;
+pub use details::{MemoryBreakdown, ProcessDetails};
+pub use manager::{Churn, ExitedProcess, ProcManager, Totals};
+pub use mydisk::MyDisk;
+pub use mynetwork::MyNetwork;
+pub use myprocess::{Metric, MyProcess, ProcId, ProcState};
+pub use mythread::MyThread;
+pub use snapshot::{Snapshot, Walk};
+pub use sorted::{ProcRow, SortType, SortedProcesses, View};
+pub use svgmaker::{SvgColors, generate_svg};
@@ -1,12 +1,11 @@
+mod affinity;
 mod config;
 #[cfg(feature = "gui")]
 mod gui;
-mod helpers;
-mod manager;
-mod mynetwork;
-mod myprocess;
-mod sorted;
-mod svgmaker;
+mod keymap;
+mod openfiles;
+mod smaps;
+mod theme;
 mod tui;
 
 use std::{
@@ -17,6 +16,8 @@ use std::{
 
 use gumdrop::Options;
 
+use toprs::{Metric, ProcManager, generate_svg};
+
 use crate::config::{Config, Mode};
 
 #[derive(Options)]
@@ -33,23 +34,49 @@ struct Args {
     #[options(help = "Print to stdout")]
     out: bool,
 
+    #[options(
+        short = "m",
+        help = "Print memory map breakdown of process to stdout",
+        meta = "<PID>"
+    )]
+    smaps: Option<usize>,
+
+    #[options(help = "Use this config file instead of the default", meta = "<FILE>")]
+    config: Option<PathBuf>,
+
+    #[options(no_short, help = "Print the effective config and exit")]
+    print_config: bool,
+
     #[options(help = "Print help message")]
     help: bool,
 }
 
 fn main() {
     let ops = Args::parse_args_default_or_exit();
-    let config = Config::load();
+    if let Some(path) = ops.config {
+        Config::set_path(path);
+    }
+    let config = Config::try_load().unwrap_or_else(|err| {
+        eprintln!("warning: using default config, {err}");
+        Config::load()
+    });
+    for warning in &config.warnings {
+        eprintln!("warning: {warning}");
+    }
     let default_mode = config.mode;
 
-    if let Some(path) = ops.svg {
-        draw_flamegraph(path);
+    if ops.print_config {
+        print!("{}", config.to_toml());
+    } else if let Some(path) = ops.svg {
+        draw_flamegraph(path, &config);
     } else if ops.gui {
         run_gui();
     } else if ops.tui {
         run_tui(config);
     } else if ops.out {
         run_output();
+    } else if let Some(pid) = ops.smaps {
+        run_output_smaps(pid);
     } else {
         //no arguments so we try load config or default
         match default_mode {
@@ -84,7 +111,7 @@ fn run_tui(config: Config) {
 }
 
 fn run_output() {
-    let man = manager::ProcManager::new();
+    let man = ProcManager::with_tracked(&Metric::ALL);
     let procs = man.procs();
     let mut lines: Vec<Vec<String>> = vec![];
     lines.push(vec![
@@ -94,19 +121,65 @@ fn run_output() {
         "memself".to_string(),
         "memchildren".to_string(),
         "memtotal".to_string(),
-        "cpu".to_string(),
     ]);
+    for metric in Metric::ALL {
+        lines[0].push(metric.key().to_string());
+        lines[0].push(format!("{}total", metric.key()));
+    }
+    lines[0].push("threads".to_string());
+    lines[0].push("state".to_string());
     for p in procs {
-        lines.push(vec![
+        let mut line = vec![
             p.name.to_string(),
             p.pid.to_string(),
             p.parent.to_string(),
             p.memory.to_string(),
             p.children_memory.to_string(),
             p.total().to_string(),
-            p.cpu.to_string(),
+        ];
+        for metric in Metric::ALL {
+            line.push(p.usage(metric).to_string());
+            line.push(p.total_usage(metric).to_string());
+        }
+        line.push(p.threads.to_string());
+        line.push(p.state.label().replace(' ', "_"));
+        lines.push(line);
+    }
+    print_table(lines);
+}
+
+fn run_output_smaps(pid: usize) {
+    let groups = match smaps::load(pid) {
+        Ok(groups) => groups,
+        Err(err) => {
+            println!("error: {err}");
+            return;
+        }
+    };
+    let mut lines: Vec<Vec<String>> = vec![];
+    lines.push(vec![
+        "kind".to_string(),
+        "count".to_string(),
+        "rss".to_string(),
+        "pss".to_string(),
+        "swap".to_string(),
+        "mapping".to_string(),
+    ]);
+    for g in groups {
+        lines.push(vec![
+            g.kind.to_string(),
+            g.count.to_string(),
+            g.rss.to_string(),
+            g.pss.to_string(),
+            g.swap.to_string(),
+            g.name,
         ]);
     }
+    print_table(lines);
+}
+
+///output each line with columns padded to the widest value
+fn print_table(lines: Vec<Vec<String>>) {
     let widths: Vec<usize> = lines[0]
         .iter()
         .enumerate()
@@ -121,11 +194,11 @@ fn run_output() {
     }
 }
 
-fn draw_flamegraph(path: PathBuf) {
-    let man = manager::ProcManager::new();
+fn draw_flamegraph(path: PathBuf, config: &Config) {
+    let man = ProcManager::new();
     let procs = man.procs();
     let file = File::create(path).expect("unable to create svg output file");
     let mut writer = BufWriter::new(file);
-    let svg = svgmaker::generate_svg(procs);
+    let svg = generate_svg(procs, &config.theme().svg_colors());
     let _ = writer.write(svg.as_bytes());
 }
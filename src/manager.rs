@@ -1,27 +1,62 @@
-use crate::{mynetwork::MyNetwork, myprocess::MyProcess};
+use crate::{
+    details::{self, ProcessDetails},
+    mydisk::MyDisk,
+    mynetwork::MyNetwork,
+    myprocess::{Metric, MyProcess, ProcId, ProcState},
+    mythread::MyThread,
+    procnet::NetTracker,
+    procstat,
+    snapshot::Snapshot,
+};
 use std::{
     cmp::Reverse,
     collections::{HashMap, HashSet},
     path::PathBuf,
     str::FromStr,
-    time::Instant,
+    time::{Duration, Instant},
 };
 use sysinfo::{
-    CpuRefreshKind, MemoryRefreshKind, Networks, ProcessRefreshKind, RefreshKind, System,
+    CpuRefreshKind, Disks, Groups, MemoryRefreshKind, Networks, Pid, ProcessRefreshKind,
+    ProcessStatus, RefreshKind, System, ThreadKind, Users,
 };
 
+///how long exited processes are kept
+const EXITED_RETENTION: Duration = Duration::from_secs(5);
+
+///Collects processes and system usage, call update to refresh
 pub struct ProcManager {
     procs: Vec<MyProcess>,
+    exited: Vec<ExitedProcess>,
+    churn: Churn,
+    last_forks: Option<u64>,
     network_data: Vec<MyNetwork>,
+    net_tracker: NetTracker,
+    tracked: Vec<Metric>, //metrics which need extra reads
+    disk_data: Vec<MyDisk>,
 
     //sysinfo objects
     networks: Networks,
+    disks: Disks,
     system: System,
+    users: Users,
+    groups: Groups, //only used for details, loaded once
     last_update: Instant,
 }
 
+impl Default for ProcManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl ProcManager {
+    ///loads all processes, cpu and disk usage are only known after the first update
     pub fn new() -> Self {
+        Self::with_tracked(&[])
+    }
+
+    ///like new, with the metrics of set_tracked already collected for the first processes
+    pub fn with_tracked(metrics: &[Metric]) -> Self {
         let mut system = System::new_with_specifics(
             RefreshKind::nothing()
                 .with_cpu(CpuRefreshKind::everything())
@@ -29,38 +64,181 @@ impl ProcManager {
                 .with_memory(MemoryRefreshKind::everything()),
         );
 
-        let mut procs = update_procs(&mut system);
+        let users = Users::new_with_refreshed_list();
+        let mut procs = update_procs(&mut system, &users, metrics.contains(&Metric::CpuTime));
         //remove all disk on the first update, as they have movement
-        procs.iter_mut().for_each(|p| p.disk = 0.0);
+        procs.iter_mut().for_each(|p| {
+            p.disk_read = 0.0;
+            p.disk_write = 0.0;
+        });
+        update_children_usage(&mut procs);
         let networks = Networks::new_with_refreshed_list();
-
+        let disks = Disks::new_with_refreshed_list();
         Self {
             procs,
+            exited: vec![],
+            churn: Churn::default(),
+            last_forks: procstat::total_forks(),
             system,
+            users,
+            groups: Groups::new_with_refreshed_list(),
             last_update: Instant::now(),
             networks,
             network_data: vec![],
+            net_tracker: NetTracker::new(),
+            tracked: metrics.to_vec(),
+            disks,
+            disk_data: vec![],
         }
     }
+    ///refresh processes, usage per second is since the previous update
     pub fn update(&mut self) {
-        self.procs = update_procs(&mut self.system);
+        self.users.refresh();
+        let exited_cpu = self.is_tracked(Metric::CpuTime);
+        let previous = std::mem::replace(
+            &mut self.procs,
+            update_procs(&mut self.system, &self.users, exited_cpu),
+        );
+        self.update_exited(previous);
+        let elapsed = Instant::now()
+            .saturating_duration_since(self.last_update)
+            .as_secs_f64();
         //calc writes per second
         self.procs.iter_mut().for_each(|p| {
-            p.disk /= Instant::now()
-                .saturating_duration_since(self.last_update)
-                .as_secs_f64()
+            p.disk_read /= elapsed;
+            p.disk_write /= elapsed;
         });
+        //calc network per second, the first update only records the socket counters
+        if self.is_tracked(Metric::NetRx) || self.is_tracked(Metric::NetTx) {
+            let net = self.net_tracker.update(&self.procs);
+            for p in self.procs.iter_mut() {
+                if let Some((received, sent)) = net.get(&p.id()) {
+                    p.net_rx = *received as f64 / elapsed;
+                    p.net_tx = *sent as f64 / elapsed;
+                }
+            }
+        } else {
+            self.net_tracker.reset();
+        }
+        update_children_usage(&mut self.procs);
         self.update_network_data();
+        self.update_disk_data();
         self.last_update = Instant::now();
     }
+    ///metrics which need extra reads on each update are only collected while tracked:
+    ///network usage, linux only, which reads the sockets of all processes
+    ///cpu time of exited children, linux only, which reads /proc/<pid>/stat of all processes
+    pub fn set_tracked(&mut self, metrics: &[Metric]) {
+        self.tracked = metrics.to_vec();
+    }
+
+    fn is_tracked(&self, metric: Metric) -> bool {
+        self.tracked.contains(&metric)
+    }
+
+    ///processes of the last update, with children values added up
     pub fn procs(&self) -> &Vec<MyProcess> {
         &self.procs
     }
 
+    ///copy of the current state, which can be kept after the next update
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot::new(self.procs.clone(), self.get_totals(), self.get_networks())
+    }
+
+    ///processes which exited recently, with their last known values
+    pub fn exited(&self) -> &[ExitedProcess] {
+        &self.exited
+    }
+
+    ///processes started and exited during the last update interval
+    pub fn churn(&self) -> &Churn {
+        &self.churn
+    }
+
+    ///diff previous and current snapshot to find started and exited processes
+    fn update_exited(&mut self, previous: Vec<MyProcess>) {
+        let now = Instant::now();
+        //a reused pid counts as one exited and one started process
+        let current: HashSet<ProcId> = self.procs.iter().map(|p| p.id()).collect();
+        let before: HashSet<ProcId> = previous.iter().map(|p| p.id()).collect();
+
+        self.exited
+            .retain(|e| now.saturating_duration_since(e.exited_at) < EXITED_RETENTION);
+        let mut exited = 0;
+        for proc in previous {
+            if !current.contains(&proc.id()) {
+                exited += 1;
+                self.exited.push(ExitedProcess {
+                    proc,
+                    exited_at: now,
+                });
+            }
+        }
+        let forks = procstat::total_forks();
+        self.churn = Churn {
+            started: current.difference(&before).count(),
+            exited,
+            forks: forks
+                .zip(self.last_forks)
+                .map(|(now, last)| now.saturating_sub(last)),
+        };
+        self.last_forks = forks;
+    }
+
+    ///full details of a process as of the last update
+    pub fn details(&self, pid: usize) -> Option<ProcessDetails> {
+        self.system
+            .process(Pid::from(pid))
+            .map(|p| details::load(pid, p, &self.users, &self.groups))
+    }
+
+    ///network interfaces, usage per second is since the previous update
     pub fn get_networks(&self) -> Vec<MyNetwork> {
         self.network_data.clone()
     }
 
+    ///mounted disks, usage per second is since the previous update
+    pub fn get_disks(&self) -> Vec<MyDisk> {
+        self.disk_data.clone()
+    }
+
+    ///threads of the process sorted by cpu usage, only available on linux
+    pub fn threads(&self, pid: usize) -> Vec<MyThread> {
+        let Some(main) = self.system.process(Pid::from(pid)) else {
+            return vec![];
+        };
+        let mut threads: Vec<MyThread> = self
+            .system
+            .processes()
+            .values()
+            .filter(|x| {
+                x.thread_kind() == Some(sysinfo::ThreadKind::Userland)
+                    && x.parent() == Some(main.pid())
+            })
+            .map(|x| MyThread {
+                tid: x.pid().into(),
+                name: x.name().to_string_lossy().to_string(),
+                state: x.status().to_string(),
+                cpu: x.cpu_usage(),
+                cpu_time: x.accumulated_cpu_time() as f64 / 1000.,
+            })
+            .collect();
+        //the process values are the sum of all its threads, so the main thread gets what remains
+        threads.push(MyThread {
+            tid: pid,
+            name: main.name().to_string_lossy().to_string(),
+            state: main.status().to_string(),
+            cpu: (main.cpu_usage() - threads.iter().map(|t| t.cpu).sum::<f32>()).max(0.),
+            cpu_time: (main.accumulated_cpu_time() as f64 / 1000.
+                - threads.iter().map(|t| t.cpu_time).sum::<f64>())
+            .max(0.),
+        });
+        threads.sort_by(|a, b| b.cpu.total_cmp(&a.cpu));
+        threads
+    }
+
+    ///system wide memory and cpu usage
     pub fn get_totals(&self) -> Totals {
         //there is a difference between the sum of the procs resident memory and total memory as per sysinfo.
         //we use sum of proc resident memory to be consistent with proc display
@@ -114,6 +292,29 @@ impl ProcManager {
             }
         }
     }
+
+    fn update_disk_data(&mut self) {
+        self.disks.refresh(true);
+        let elapsed = Instant::now()
+            .saturating_duration_since(self.last_update)
+            .as_secs_f64();
+        self.disk_data = self
+            .disks
+            .list()
+            .iter()
+            .map(|d| {
+                let usage = d.usage();
+                MyDisk {
+                    name: d.name().to_string_lossy().to_string(),
+                    mount_point: d.mount_point().to_string_lossy().to_string(),
+                    total: d.total_space(),
+                    available: d.available_space(),
+                    read_per_sec: (usage.read_bytes as f64 / elapsed) as u64,
+                    written_per_sec: (usage.written_bytes as f64 / elapsed) as u64,
+                }
+            })
+            .collect();
+    }
 }
 
 trait NoNan {
@@ -125,13 +326,44 @@ impl NoNan for f32 {
     }
 }
 
+///Process which exited recently
+#[non_exhaustive]
+pub struct ExitedProcess {
+    ///last known values
+    pub proc: MyProcess,
+    ///when the exit was noticed
+    pub exited_at: Instant,
+}
+
+///Processes started and exited during the last update interval
+#[derive(Default, Clone)]
+#[non_exhaustive]
+pub struct Churn {
+    ///processes which started
+    pub started: usize,
+    ///processes which exited
+    pub exited: usize,
+    ///all forks incl. threads and short lived processes, linux only
+    pub forks: Option<u64>,
+}
+
+///System wide usage
+#[derive(Clone, Debug, Default)]
+#[non_exhaustive]
 pub struct Totals {
+    ///sum of all processes
     pub memory_procs: u64,
+    ///used memory as reported by the system
     pub memory_used: u64,
+    ///installed memory
     pub memory_total: u64,
+    ///average usage of all cores in percent
     pub cpu_avg: f32,
+    ///number of cores
     pub cpu_count: usize,
+    ///seconds
     pub uptime: u64,
+    ///usage of each core
     pub cpus: Vec<f32>,
 }
 
@@ -152,12 +384,16 @@ fn update_children_usage(procs: &mut [MyProcess]) {
         }
         //add itself plus its own children to the parent
         if let Some(&parent_idx) = index_map.get(&parent) {
-            procs[parent_idx].children_memory += procs[i].memory + procs[i].children_memory;
+            procs[parent_idx].children_memory += procs[i].total();
+            for metric in Metric::ALL {
+                procs[parent_idx].children_usage[metric as usize] += procs[i].total_usage(metric);
+            }
         }
     }
 }
 
-fn update_procs(sys: &mut System) -> Vec<MyProcess> {
+///exited_cpu adds the cpu time of exited children
+fn update_procs(sys: &mut System, users: &Users, exited_cpu: bool) -> Vec<MyProcess> {
     sys.refresh_all();
     let mut procs = sys
         .processes()
@@ -194,41 +430,48 @@ fn update_procs(sys: &mut System) -> Vec<MyProcess> {
             };
             // let full_cmd = format!("{cmd} | {}", long_cmd.join(" "));
 
+            let pid = x.pid().into();
+            //children which already exited are counted, so they are included in the tree total
+            let mut children_usage = [0.; Metric::COUNT];
+            if exited_cpu && let Some(stat) = procstat::read(pid) {
+                children_usage[Metric::CpuTime as usize] = stat.exited_children_cpu_time();
+            }
+
             MyProcess {
-                pid: x.pid().into(),
+                pid,
                 parent: x.parent().map_or(0, |f| f.into()),
                 name: x.name().to_string_lossy().to_string(),
                 command: long_cmd.join(" "),
                 command_display: full_cmd,
+                user: x
+                    .user_id()
+                    .map(|uid| match users.get_user_by_id(uid) {
+                        Some(user) => user.name().to_string(),
+                        None => (**uid).to_string(),
+                    })
+                    .unwrap_or_default(),
                 memory: x.memory(),
                 cpu: x.cpu_usage(),
+                cpu_time: x.accumulated_cpu_time() as f64 / 1000.,
+                children_usage,
                 children_memory: 0,
                 run_time: x.run_time(),
+                start_time: x.start_time(),
+                threads: x.tasks().map_or(0, |tasks| tasks.len() + 1), //tasks exclude the main thread
+                state: proc_state(x.status()),
+                kernel_thread: x.thread_kind() == Some(ThreadKind::Kernel),
                 depth: 0,
-                disk: (x.disk_usage().read_bytes + x.disk_usage().written_bytes) as f64,
+                disk_read: x.disk_usage().read_bytes as f64,
+                disk_write: x.disk_usage().written_bytes as f64,
+                disk_read_bytes: x.disk_usage().total_read_bytes,
+                disk_written_bytes: x.disk_usage().total_written_bytes,
+                ..Default::default()
             }
         })
         .filter(|x| x.pid != 0) //dont want root or errors
         .collect::<Vec<MyProcess>>();
 
-    //break cycle loops parent 1 -> child 2 -> parent 1
-    //not sure why this happens, possibly reuse of pid's
-    //if there is a loop, we make the lowest pid's parent, equal to 0
-    //using lowest pid is not entirely correct, but we must break cycle somewhere
-    //TODO: find actual parent which no longer exists
-    let mut change = HashSet::new();
-    for proc in procs.iter() {
-        let parents = parents_limited(proc.pid, &procs, HashSet::new());
-        //if parents contains this pid, this pid is looped, so we make it 0 if it is the lowest
-        if parents.contains(&proc.pid) && parents.iter().min().unwrap() == &proc.pid {
-            change.insert(proc.pid);
-        }
-    }
-    for proc in procs.iter_mut() {
-        if change.contains(&proc.pid) {
-            proc.parent = 0;
-        }
-    }
+    fix_parents(&mut procs);
 
     add_depths(&mut procs);
 
@@ -242,11 +485,58 @@ fn update_procs(sys: &mut System) -> Vec<MyProcess> {
 
     procs.sort_by_key(|a| Reverse(a.memory));
 
-    update_children_usage(&mut procs);
-
     procs
 }
 
+fn proc_state(status: ProcessStatus) -> ProcState {
+    match status {
+        ProcessStatus::Run | ProcessStatus::Waking => ProcState::Running,
+        ProcessStatus::Sleep | ProcessStatus::Parked => ProcState::Sleeping,
+        ProcessStatus::UninterruptibleDiskSleep | ProcessStatus::LockBlocked => {
+            ProcState::DiskSleep
+        }
+        ProcessStatus::Stop | ProcessStatus::Tracing | ProcessStatus::Suspended => {
+            ProcState::Stopped
+        }
+        ProcessStatus::Zombie | ProcessStatus::Dead => ProcState::Zombie,
+        ProcessStatus::Idle => ProcState::Idle,
+        _ => ProcState::Other,
+    }
+}
+
+///the parent pid of a process may have been reused after the actual parent exited,
+///this gives loops like parent 1 -> child 2 -> parent 1.
+///a parent can not have started after its child, so those are moved to the root
+///the start time of the remaining parents is kept, so parents are identified by ProcId
+fn fix_parents(procs: &mut [MyProcess]) {
+    let start_times: HashMap<usize, u64> = procs.iter().map(|p| (p.pid, p.start_time)).collect();
+    for proc in procs.iter_mut() {
+        if start_times
+            .get(&proc.parent)
+            .is_some_and(|&start| start > proc.start_time)
+        {
+            proc.parent = 0;
+        }
+    }
+
+    //start times only have a resolution of seconds, so a loop within the same second is still possible
+    //in that case we must break the cycle somewhere, so make the lowest pid's parent 0
+    let mut change = HashSet::new();
+    for proc in procs.iter() {
+        let parents = parents_limited(proc.pid, procs, HashSet::new());
+        //if parents contains this pid, this pid is looped, so we make it 0 if it is the lowest
+        if parents.contains(&proc.pid) && parents.iter().min().unwrap() == &proc.pid {
+            change.insert(proc.pid);
+        }
+    }
+    for proc in procs.iter_mut() {
+        if change.contains(&proc.pid) {
+            proc.parent = 0;
+        }
+        proc.parent_start_time = start_times.get(&proc.parent).copied().unwrap_or_default();
+    }
+}
+
 ///add depths to processes
 //we process each item, and walk up to parent to count the steps to root, this is out depth
 //to speed up, we cache the depth of each item's parent when we visit them the first time, so we dont need to walk them again
@@ -359,5 +649,54 @@ mod tests {
         update_children_usage(&mut procs);
         assert_eq!(procs[1].children_memory, 90); // 2 -> 4+5
         assert_eq!(procs[0].children_memory, 140); // 1 -> 2+3 (+ 4+5)
+
+        // other metrics use the same rollup: 1 -> 2(cpu 10, read 5) -> 3(cpu 20)
+        let mut procs = vec![proc(1, 0, 0, 0), proc(2, 1, 0, 0), proc(3, 2, 0, 0)];
+        procs[1].cpu = 10.;
+        procs[1].disk_read = 5.;
+        procs[2].cpu = 20.;
+        add_depths(&mut procs);
+        update_children_usage(&mut procs);
+        assert_eq!(procs[0].children(Metric::Cpu), 30.);
+        assert_eq!(procs[1].children(Metric::Cpu), 20.);
+        assert_eq!(procs[1].total_usage(Metric::Cpu), 30.);
+        assert_eq!(procs[0].total_usage(Metric::DiskRead), 5.);
+
+        // cpu time of exited children is in children_usage before the rollup:
+        // 1(cpu time 1) -> 2(2, exited children 4) -> 3(8, exited children 16)
+        let mut procs = vec![proc(1, 0, 0, 0), proc(2, 1, 0, 0), proc(3, 2, 0, 0)];
+        for (p, (own, exited)) in procs.iter_mut().zip([(1., 0.), (2., 4.), (8., 16.)]) {
+            p.cpu_time = own;
+            p.children_usage[Metric::CpuTime as usize] = exited;
+        }
+        add_depths(&mut procs);
+        update_children_usage(&mut procs);
+        assert_eq!(procs[2].total_usage(Metric::CpuTime), 24.);
+        assert_eq!(procs[1].children(Metric::CpuTime), 28.); // 4 + 8 + 16
+        assert_eq!(procs[0].total_usage(Metric::CpuTime), 31.);
+    }
+
+    #[test]
+    fn test_fix_parents() {
+        // 1 was started before 2, so 2 -> 1 is correct but 1 -> 2 is a reused pid
+        let mut procs = vec![proc(1, 2, 0, 0), proc(2, 1, 0, 0), proc(3, 2, 0, 0)];
+        procs[0].start_time = 100;
+        procs[1].start_time = 200;
+        procs[2].start_time = 300;
+        fix_parents(&mut procs);
+        assert_eq!(
+            procs.iter().map(|p| p.parent).collect::<Vec<_>>(),
+            vec![0, 1, 2]
+        );
+        assert_eq!(procs[2].parent_id(), procs[1].id());
+        assert_eq!(procs[0].parent_start_time, 0);
+
+        // same start time, so fall back to breaking the loop at the lowest pid
+        let mut procs = vec![proc(5, 6, 0, 0), proc(6, 5, 0, 0)];
+        fix_parents(&mut procs);
+        assert_eq!(
+            procs.iter().map(|p| p.parent).collect::<Vec<_>>(),
+            vec![0, 5]
+        );
     }
 }
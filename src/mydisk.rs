@@ -0,0 +1,24 @@
+///Mounted disk, in bytes
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct MyDisk {
+    ///device name
+    pub name: String,
+    ///where it is mounted
+    pub mount_point: String,
+    ///size
+    pub total: u64,
+    ///free space available to users
+    pub available: u64,
+    ///read per second
+    pub read_per_sec: u64,
+    ///written per second
+    pub written_per_sec: u64,
+}
+
+impl MyDisk {
+    ///total minus available
+    pub fn used(&self) -> u64 {
+        self.total.saturating_sub(self.available)
+    }
+}
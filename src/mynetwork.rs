@@ -1,8 +1,15 @@
+///Network interface, in bytes
 #[derive(Debug, Clone)]
+#[non_exhaustive]
 pub struct MyNetwork {
+    ///interface name
     pub name: String,
+    ///received since the manager was created
     pub received: u64,
+    ///sent since the manager was created
     pub sent: u64,
+    ///received per second
     pub received_per_sec: u64,
+    ///sent per second
     pub sent_per_sec: u64,
 }
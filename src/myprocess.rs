@@ -1,20 +1,223 @@
+use serde::{Deserialize, Serialize};
+
+use crate::helpers::{nice_cpu_time, nice_size};
+
+///A process with memory and usage of its own and of all its descendants
 #[derive(Clone, Debug, Default)]
+#[non_exhaustive]
 pub struct MyProcess {
+    ///process id
     pub pid: usize,
+    ///pid of the parent, 0 if none
     pub parent: usize,
+    ///start time of the parent, so it is still identified after its pid is reused
+    pub parent_start_time: u64,
+    ///executable name
     pub name: String,
+    ///full command line
     pub command: String,
-    pub command_display: String, //for table view
+    ///for table view
+    pub command_display: String,
+    ///name, or id if unknown
+    pub user: String,
+    ///own resident memory in bytes
     pub memory: u64,
+    ///cpu usage in percent of one core
     pub cpu: f32,
-    pub disk: f64,
+    ///seconds of user+system cpu used since process start
+    pub cpu_time: f64,
+    ///bytes read per second
+    pub disk_read: f64,
+    ///bytes written per second
+    pub disk_write: f64,
+    ///bytes read since process start
+    pub disk_read_bytes: u64,
+    ///bytes written since process start
+    pub disk_written_bytes: u64,
+    ///bytes received per second
+    pub net_rx: f64,
+    ///bytes sent per second
+    pub net_tx: f64,
+    ///memory of all descendants
     pub children_memory: u64,
+    ///sum of all descendants, indexed by metric
+    pub children_usage: [f64; Metric::COUNT],
+    ///depth in the tree, 1 for roots
     pub depth: usize,
+    ///seconds since start
     pub run_time: u64,
+    ///seconds since epoch
+    pub start_time: u64,
+    ///0 if unknown
+    pub threads: usize,
+    ///scheduling state
+    pub state: ProcState,
+    ///only detected on linux
+    pub kernel_thread: bool,
+}
+
+///Scheduling state of a process
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug, Default, Serialize, Deserialize)]
+pub enum ProcState {
+    Running,
+    Sleeping,
+    ///uninterruptible, usually waiting for io
+    DiskSleep,
+    Stopped,
+    Zombie,
+    Idle,
+    #[default]
+    Other,
+}
+
+impl ProcState {
+    ///all states, in the order they are shown
+    pub const ALL: [ProcState; 7] = [
+        ProcState::Running,
+        ProcState::Sleeping,
+        ProcState::DiskSleep,
+        ProcState::Stopped,
+        ProcState::Zombie,
+        ProcState::Idle,
+        ProcState::Other,
+    ];
+
+    ///lowercase name for display
+    pub fn label(&self) -> &'static str {
+        match self {
+            ProcState::Running => "running",
+            ProcState::Sleeping => "sleeping",
+            ProcState::DiskSleep => "disk wait",
+            ProcState::Stopped => "stopped",
+            ProcState::Zombie => "zombie",
+            ProcState::Idle => "idle",
+            ProcState::Other => "other",
+        }
+    }
+}
+
+///Identifies a process, pids alone can be reused after a process exits
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default, Serialize, Deserialize)]
+pub struct ProcId {
+    ///process id
+    pub pid: usize,
+    ///seconds since epoch
+    pub start_time: u64,
+}
+
+///Metrics which are added up over the tree, like memory
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Metric {
+    Cpu,
+    CpuTime,
+    DiskRead,
+    DiskWrite,
+    DiskReadBytes,
+    DiskWrittenBytes,
+    NetRx,
+    NetTx,
+}
+
+impl Metric {
+    ///number of metrics
+    pub const COUNT: usize = 8;
+    ///all metrics, in column order
+    pub const ALL: [Metric; Metric::COUNT] = [
+        Metric::Cpu,
+        Metric::CpuTime,
+        Metric::DiskRead,
+        Metric::DiskWrite,
+        Metric::DiskReadBytes,
+        Metric::DiskWrittenBytes,
+        Metric::NetRx,
+        Metric::NetTx,
+    ];
+
+    ///short name used in output
+    pub fn key(&self) -> &'static str {
+        match self {
+            Metric::Cpu => "cpu",
+            Metric::CpuTime => "cputime",
+            Metric::DiskRead => "diskread",
+            Metric::DiskWrite => "diskwrite",
+            Metric::DiskReadBytes => "diskreadbytes",
+            Metric::DiskWrittenBytes => "diskwrittenbytes",
+            Metric::NetRx => "netrx",
+            Metric::NetTx => "nettx",
+        }
+    }
+
+    ///name for headers and help
+    pub fn label(&self) -> &'static str {
+        match self {
+            Metric::Cpu => "CPU",
+            Metric::CpuTime => "CPU time",
+            Metric::DiskRead => "Disk read",
+            Metric::DiskWrite => "Disk write",
+            Metric::DiskReadBytes => "Disk read since start",
+            Metric::DiskWrittenBytes => "Disk written since start",
+            Metric::NetRx => "Net ↓",
+            Metric::NetTx => "Net ↑",
+        }
+    }
+
+    ///blank if zero, like the memory columns
+    pub fn format(&self, val: f64) -> String {
+        if val == 0. {
+            return String::new();
+        }
+        match self {
+            Metric::Cpu => format!("{val:.1}%"),
+            Metric::CpuTime => nice_cpu_time(val),
+            Metric::DiskReadBytes | Metric::DiskWrittenBytes => nice_size(val as u64),
+            _ => format!("{}/s", nice_size(val as u64)),
+        }
+    }
 }
 
 impl MyProcess {
+    ///pid and start time, which stays unique when pids are reused
+    pub fn id(&self) -> ProcId {
+        ProcId {
+            pid: self.pid,
+            start_time: self.start_time,
+        }
+    }
+
+    ///parent and its start time, the parent may have exited
+    pub fn parent_id(&self) -> ProcId {
+        ProcId {
+            pid: self.parent,
+            start_time: self.parent_start_time,
+        }
+    }
+
+    ///own memory plus all descendants
     pub fn total(&self) -> u64 {
         self.memory + self.children_memory
     }
+
+    ///own value of the metric
+    pub fn usage(&self, metric: Metric) -> f64 {
+        match metric {
+            Metric::Cpu => self.cpu as f64,
+            Metric::CpuTime => self.cpu_time,
+            Metric::DiskRead => self.disk_read,
+            Metric::DiskWrite => self.disk_write,
+            Metric::DiskReadBytes => self.disk_read_bytes as f64,
+            Metric::DiskWrittenBytes => self.disk_written_bytes as f64,
+            Metric::NetRx => self.net_rx,
+            Metric::NetTx => self.net_tx,
+        }
+    }
+
+    ///sum of the metric over all descendants
+    pub fn children(&self, metric: Metric) -> f64 {
+        self.children_usage[metric as usize]
+    }
+
+    ///own value plus all descendants
+    pub fn total_usage(&self, metric: Metric) -> f64 {
+        self.usage(metric) + self.children(metric)
+    }
 }
@@ -0,0 +1,15 @@
+///Thread of a process
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct MyThread {
+    ///thread id
+    pub tid: usize,
+    ///thread name
+    pub name: String,
+    ///scheduling state as reported by the system
+    pub state: String,
+    ///cpu usage in percent of one core
+    pub cpu: f32,
+    ///seconds of user+system cpu used since thread start
+    pub cpu_time: f64,
+}
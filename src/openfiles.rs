@@ -0,0 +1,234 @@
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    fs,
+    net::{Ipv4Addr, Ipv6Addr},
+};
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, PartialOrd, Ord)]
+pub enum FdKind {
+    File,
+    Pipe,
+    Socket,
+    AnonInode,
+    Other,
+}
+
+impl Display for FdKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            FdKind::File => "file",
+            FdKind::Pipe => "pipe",
+            FdKind::Socket => "socket",
+            FdKind::AnonInode => "anon",
+            FdKind::Other => "other",
+        };
+        write!(f, "{name}")
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SocketInfo {
+    pub protocol: String,
+    pub local: String,
+    pub remote: String,
+    pub state: String,
+}
+
+#[derive(Clone, Debug)]
+pub struct OpenFd {
+    pub fd: usize,
+    pub kind: FdKind,
+    pub target: String, //what the fd links to e.g. path or socket:[123]
+    pub socket: Option<SocketInfo>, //only if found in /proc/<pid>/net
+}
+
+///lists open file descriptors of process, only available on linux
+pub fn load(pid: usize) -> Result<Vec<OpenFd>, String> {
+    let dir = fs::read_dir(format!("/proc/{pid}/fd"))
+        .map_err(|e| format!("unable to read open files of {pid}: {e}"))?;
+    let sockets = read_sockets(pid);
+
+    let mut fds: Vec<OpenFd> = dir
+        .flatten()
+        .filter_map(|entry| {
+            let fd = entry.file_name().to_string_lossy().parse().ok()?;
+            let target = fs::read_link(entry.path())
+                .ok()?
+                .to_string_lossy()
+                .to_string();
+            let kind = if target.starts_with("socket:") {
+                FdKind::Socket
+            } else if target.starts_with("pipe:") {
+                FdKind::Pipe
+            } else if target.starts_with("anon_inode:") {
+                FdKind::AnonInode
+            } else if target.starts_with('/') {
+                FdKind::File
+            } else {
+                FdKind::Other
+            };
+            let socket = target
+                .strip_prefix("socket:[")
+                .and_then(|inode| inode.strip_suffix(']'))
+                .and_then(|inode| sockets.get(inode))
+                .cloned();
+            Some(OpenFd {
+                fd,
+                kind,
+                target,
+                socket,
+            })
+        })
+        .collect();
+    fds.sort_by_key(|f| f.fd);
+    Ok(fds)
+}
+
+///number of fds per kind
+pub fn counts(fds: &[OpenFd]) -> Vec<(FdKind, usize)> {
+    let mut counts: HashMap<FdKind, usize> = HashMap::new();
+    for fd in fds {
+        *counts.entry(fd.kind).or_default() += 1;
+    }
+    let mut counts: Vec<_> = counts.into_iter().collect();
+    counts.sort();
+    counts
+}
+
+///socket inode -> socket info, from the network namespace of the process
+fn read_sockets(pid: usize) -> HashMap<String, SocketInfo> {
+    let mut sockets = HashMap::new();
+    for protocol in ["tcp", "tcp6", "udp", "udp6"] {
+        let contents =
+            fs::read_to_string(format!("/proc/{pid}/net/{protocol}")).unwrap_or_default();
+        sockets.extend(parse_inet(protocol, &contents));
+    }
+    let contents = fs::read_to_string(format!("/proc/{pid}/net/unix")).unwrap_or_default();
+    sockets.extend(parse_unix(&contents));
+    sockets
+}
+
+///parses /proc/net/tcp style tables
+fn parse_inet(protocol: &str, contents: &str) -> Vec<(String, SocketInfo)> {
+    contents
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let cols: Vec<&str> = line.split_whitespace().collect();
+            let inode = cols.get(9)?;
+            let state = if protocol.starts_with("tcp") {
+                tcp_state(cols.get(3)?).to_string()
+            } else {
+                String::new()
+            };
+            Some((
+                inode.to_string(),
+                SocketInfo {
+                    protocol: protocol.to_string(),
+                    local: parse_address(cols.get(1)?)?,
+                    remote: parse_address(cols.get(2)?)?,
+                    state,
+                },
+            ))
+        })
+        .collect()
+}
+
+///parses /proc/net/unix, path is empty for unnamed sockets
+fn parse_unix(contents: &str) -> Vec<(String, SocketInfo)> {
+    contents
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let cols: Vec<&str> = line.split_whitespace().collect();
+            let inode = cols.get(6)?;
+            let state = match *cols.get(5)? {
+                "01" => "UNCONNECTED",
+                "02" => "CONNECTING",
+                "03" => "CONNECTED",
+                "04" => "DISCONNECTING",
+                _ => "",
+            };
+            Some((
+                inode.to_string(),
+                SocketInfo {
+                    protocol: "unix".to_string(),
+                    local: cols.get(7).unwrap_or(&"").to_string(),
+                    remote: String::new(),
+                    state: state.to_string(),
+                },
+            ))
+        })
+        .collect()
+}
+
+///"0100007F:0035" -> "127.0.0.1:53", ipv6 addresses are 4 little endian words
+fn parse_address(val: &str) -> Option<String> {
+    let (addr, port) = val.split_once(':')?;
+    let port = u16::from_str_radix(port, 16).ok()?;
+    match addr.len() {
+        8 => {
+            let ip = Ipv4Addr::from(u32::from_str_radix(addr, 16).ok()?.swap_bytes());
+            Some(format!("{ip}:{port}"))
+        }
+        32 => {
+            let mut bytes = [0u8; 16];
+            for (i, chunk) in bytes.chunks_mut(4).enumerate() {
+                let word = u32::from_str_radix(&addr[i * 8..i * 8 + 8], 16).ok()?;
+                chunk.copy_from_slice(&word.to_le_bytes());
+            }
+            Some(format!("[{}]:{port}", Ipv6Addr::from(bytes)))
+        }
+        _ => None,
+    }
+}
+
+fn tcp_state(val: &str) -> &'static str {
+    match val {
+        "01" => "ESTABLISHED",
+        "02" => "SYN_SENT",
+        "03" => "SYN_RECV",
+        "04" => "FIN_WAIT1",
+        "05" => "FIN_WAIT2",
+        "06" => "TIME_WAIT",
+        "07" => "CLOSE",
+        "08" => "CLOSE_WAIT",
+        "09" => "LAST_ACK",
+        "0A" => "LISTEN",
+        "0B" => "CLOSING",
+        _ => "UNKNOWN",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_sockets() {
+        let tcp = "  sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode
+   0: 0100007F:0035 00000000:0000 0A 00000000:00000000 00:00000000 00000000 0        0 927 1 0000000000000000 100 0 0 10 0
+   1: 0100007F:BC8F 0101A8C0:01BB 01 00000000:00000000 00:00000000 00000000 0        0 928 1 0000000000000000 100 0 0 10 0";
+        let sockets = parse_inet("tcp", tcp);
+        assert_eq!(sockets.len(), 2);
+        assert_eq!(sockets[0].0, "927");
+        assert_eq!(sockets[0].1.local, "127.0.0.1:53");
+        assert_eq!(sockets[0].1.state, "LISTEN");
+        assert_eq!(sockets[1].1.remote, "192.168.1.1:443");
+        assert_eq!(sockets[1].1.state, "ESTABLISHED");
+
+        assert_eq!(
+            parse_address("00000000000000000000000001000000:0050").as_deref(),
+            Some("[::1]:80")
+        );
+
+        let unix = "Num       RefCount Protocol Flags    Type St Inode Path
+0000000027cae0c6: 00000003 00000000 00000000 0001 03   658 /run/dbus/socket
+00000000ed5ad825: 00000003 00000000 00000000 0001 03   926";
+        let sockets = parse_unix(unix);
+        assert_eq!(sockets[0].0, "658");
+        assert_eq!(sockets[0].1.local, "/run/dbus/socket");
+        assert_eq!(sockets[1].1.state, "CONNECTED");
+    }
+}
@@ -0,0 +1,303 @@
+use std::collections::HashMap;
+
+use crate::myprocess::{MyProcess, ProcId};
+
+///Attributes tcp traffic to processes using the byte counters of each socket
+///only available on linux, and needs root to see other users' processes
+pub struct NetTracker {
+    last: HashMap<u32, SocketBytes>, //socket inode -> counters at last update
+    primed: bool,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct SocketBytes {
+    id: ProcId, //owner, the pid alone may have been reused
+    received: u64,
+    sent: u64,
+}
+
+impl NetTracker {
+    pub fn new() -> Self {
+        Self {
+            last: HashMap::new(),
+            primed: false,
+        }
+    }
+
+    ///forget the counters, the next update only records them again
+    pub fn reset(&mut self) {
+        self.last.clear();
+        self.primed = false;
+    }
+
+    ///bytes (received, sent) per process since the last update
+    pub fn update(&mut self, procs: &[MyProcess]) -> HashMap<ProcId, (u64, u64)> {
+        self.diff(read_sockets(procs))
+    }
+
+    fn diff(&mut self, current: HashMap<u32, SocketBytes>) -> HashMap<ProcId, (u64, u64)> {
+        let mut per_proc: HashMap<ProcId, (u64, u64)> = HashMap::new();
+        for (key, now) in &current {
+            let (received, sent) = match self.last.get(key) {
+                Some(last) if last.id == now.id => (
+                    now.received.saturating_sub(last.received),
+                    now.sent.saturating_sub(last.sent),
+                ),
+                //new connection since last update, so all of its traffic happened in this interval
+                _ if self.primed => (now.received, now.sent),
+                _ => (0, 0),
+            };
+            let entry = per_proc.entry(now.id).or_default();
+            entry.0 += received;
+            entry.1 += sent;
+        }
+        self.last = current;
+        self.primed = true;
+        per_proc
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn read_sockets(_procs: &[MyProcess]) -> HashMap<u32, SocketBytes> {
+    HashMap::new()
+}
+
+///counters of tcp sockets by inode, with the process owning them
+///the byte counters are only exposed by sock_diag, /proc/net/tcp has the sockets without them
+#[cfg(target_os = "linux")]
+fn read_sockets(procs: &[MyProcess]) -> HashMap<u32, SocketBytes> {
+    let mut counters = HashMap::new();
+    for family in [libc::AF_INET, libc::AF_INET6] {
+        diag::dump(family as u8, &mut counters);
+    }
+    if counters.is_empty() {
+        return HashMap::new();
+    }
+    let ids: HashMap<usize, ProcId> = procs.iter().map(|p| (p.pid, p.id())).collect();
+    socket_owners()
+        .into_iter()
+        .filter_map(|(inode, pid)| {
+            let (received, sent) = counters.get(&inode)?;
+            Some((
+                inode,
+                SocketBytes {
+                    id: *ids.get(&pid)?,
+                    received: *received,
+                    sent: *sent,
+                },
+            ))
+        })
+        .collect()
+}
+
+///socket inode to pid, from the links in /proc/<pid>/fd
+///a socket shared after fork is given to the lowest pid
+#[cfg(target_os = "linux")]
+fn socket_owners() -> HashMap<u32, usize> {
+    let mut owners: HashMap<u32, usize> = HashMap::new();
+    let Ok(entries) = std::fs::read_dir("/proc") else {
+        return owners;
+    };
+    for entry in entries.flatten() {
+        let Some(pid) = entry.file_name().to_str().and_then(|n| n.parse().ok()) else {
+            continue;
+        };
+        let Ok(fds) = std::fs::read_dir(entry.path().join("fd")) else {
+            continue;
+        };
+        for fd in fds.flatten() {
+            let inode = std::fs::read_link(fd.path()).ok().and_then(|link| {
+                link.to_str()?
+                    .strip_prefix("socket:[")?
+                    .strip_suffix(']')?
+                    .parse()
+                    .ok()
+            });
+            if let Some(inode) = inode {
+                let owner = owners.entry(inode).or_insert(pid);
+                *owner = (*owner).min(pid);
+            }
+        }
+    }
+    owners
+}
+
+///minimal sock_diag netlink client, asks for tcp_info of every tcp socket
+#[cfg(target_os = "linux")]
+mod diag {
+    use std::collections::HashMap;
+
+    const SOCK_DIAG_BY_FAMILY: u16 = 20;
+    const INET_DIAG_INFO: u16 = 2;
+    const NLMSG_ERROR: u16 = 2;
+    const NLMSG_DONE: u16 = 3;
+    const HEADER_LEN: usize = 16; //nlmsghdr
+    const MSG_LEN: usize = 72; //inet_diag_msg
+    const INODE_OFFSET: usize = 68;
+    const BYTES_ACKED_OFFSET: usize = 120; //in tcp_info
+    const BYTES_RECEIVED_OFFSET: usize = 128;
+
+    ///adds (received, sent) of each socket of the family by inode
+    pub fn dump(family: u8, counters: &mut HashMap<u32, (u64, u64)>) {
+        use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+
+        let fd = unsafe {
+            libc::socket(
+                libc::AF_NETLINK,
+                libc::SOCK_DGRAM | libc::SOCK_CLOEXEC,
+                libc::NETLINK_SOCK_DIAG,
+            )
+        };
+        if fd < 0 {
+            return;
+        }
+        let fd = unsafe { OwnedFd::from_raw_fd(fd) };
+        let request = request(family);
+        let mut addr: libc::sockaddr_nl = unsafe { std::mem::zeroed() };
+        addr.nl_family = libc::AF_NETLINK as u16;
+        let sent = unsafe {
+            libc::sendto(
+                fd.as_raw_fd(),
+                request.as_ptr().cast(),
+                request.len(),
+                0,
+                (&addr as *const libc::sockaddr_nl).cast(),
+                std::mem::size_of::<libc::sockaddr_nl>() as u32,
+            )
+        };
+        if sent < 0 {
+            return;
+        }
+        let mut buf = vec![0u8; 32 * 1024];
+        loop {
+            let len = unsafe { libc::recv(fd.as_raw_fd(), buf.as_mut_ptr().cast(), buf.len(), 0) };
+            if len <= 0 || parse(&buf[..len as usize], counters) {
+                return;
+            }
+        }
+    }
+
+    ///nlmsghdr followed by inet_diag_req_v2 for all tcp sockets of the family
+    pub fn request(family: u8) -> Vec<u8> {
+        let len = (HEADER_LEN + 56) as u32;
+        let flags = (libc::NLM_F_REQUEST | libc::NLM_F_DUMP) as u16;
+        let mut req = Vec::with_capacity(len as usize);
+        req.extend(len.to_ne_bytes());
+        req.extend(SOCK_DIAG_BY_FAMILY.to_ne_bytes());
+        req.extend(flags.to_ne_bytes());
+        req.extend([0u8; 8]); //sequence and port
+        req.extend([
+            family,
+            libc::IPPROTO_TCP as u8,
+            1 << (INET_DIAG_INFO - 1),
+            0,
+        ]);
+        req.extend(u32::MAX.to_ne_bytes()); //all states
+        req.extend([0u8; 48]); //inet_diag_sockid, unused for dumps
+        req
+    }
+
+    ///reads the messages of one datagram, returns true when the dump is done
+    pub fn parse(buf: &[u8], counters: &mut HashMap<u32, (u64, u64)>) -> bool {
+        let u16_at = |b: &[u8], i: usize| u16::from_ne_bytes([b[i], b[i + 1]]);
+        let u32_at = |b: &[u8], i: usize| u32::from_ne_bytes(b[i..i + 4].try_into().unwrap());
+        let u64_at = |b: &[u8], i: usize| u64::from_ne_bytes(b[i..i + 8].try_into().unwrap());
+        let mut offset = 0;
+        while offset + HEADER_LEN <= buf.len() {
+            let len = u32_at(buf, offset) as usize;
+            if len < HEADER_LEN || offset + len > buf.len() {
+                return true;
+            }
+            match u16_at(buf, offset + 4) {
+                NLMSG_DONE | NLMSG_ERROR => return true,
+                _ if len >= HEADER_LEN + MSG_LEN => {
+                    let msg = &buf[offset + HEADER_LEN..offset + len];
+                    let inode = u32_at(msg, INODE_OFFSET);
+                    //attributes follow the message, each padded to 4 bytes
+                    let mut attr = MSG_LEN;
+                    while attr + 4 <= msg.len() {
+                        let attr_len = u16_at(msg, attr) as usize;
+                        if attr_len < 4 || attr + attr_len > msg.len() {
+                            break;
+                        }
+                        //older kernels send a shorter tcp_info without the byte counters
+                        if u16_at(msg, attr + 2) == INET_DIAG_INFO
+                            && attr_len >= 4 + BYTES_RECEIVED_OFFSET + 8
+                        {
+                            let info = &msg[attr + 4..attr + attr_len];
+                            counters.insert(
+                                inode,
+                                (
+                                    u64_at(info, BYTES_RECEIVED_OFFSET),
+                                    u64_at(info, BYTES_ACKED_OFFSET),
+                                ),
+                            );
+                        }
+                        attr += attr_len.next_multiple_of(4);
+                    }
+                }
+                _ => {}
+            }
+            offset += len.next_multiple_of(4);
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_parse_diag() {
+        //one socket with inode 42 and a tcp_info attribute, then done
+        let mut info = vec![0u8; 232];
+        info[120..128].copy_from_slice(&8549476u64.to_ne_bytes());
+        info[128..136].copy_from_slice(&1532881u64.to_ne_bytes());
+        let mut msg = vec![0u8; 72];
+        msg[68..72].copy_from_slice(&42u32.to_ne_bytes());
+        msg.extend(((info.len() + 4) as u16).to_ne_bytes());
+        msg.extend(2u16.to_ne_bytes());
+        msg.extend(info);
+        let mut buf = vec![];
+        buf.extend(((msg.len() + 16) as u32).to_ne_bytes());
+        buf.extend(20u16.to_ne_bytes());
+        buf.extend([0u8; 10]);
+        buf.extend(msg);
+        let mut counters = HashMap::new();
+        assert!(!diag::parse(&buf, &mut counters));
+        assert_eq!(counters[&42], (1532881, 8549476));
+
+        let mut done = 16u32.to_ne_bytes().to_vec();
+        done.extend(3u16.to_ne_bytes());
+        done.extend([0u8; 10]);
+        assert!(diag::parse(&done, &mut counters));
+        assert_eq!(diag::request(libc::AF_INET as u8).len(), 72);
+    }
+
+    #[test]
+    fn test_tracker() {
+        let id = |pid| ProcId { pid, start_time: 1 };
+        let socket = |pid, received, sent| SocketBytes {
+            id: id(pid),
+            received,
+            sent,
+        };
+        let mut tracker = NetTracker::new();
+        //the first update only records the counters
+        let first = tracker.diff(HashMap::from([(1, socket(10, 100, 50))]));
+        assert_eq!(first[&id(10)], (0, 0));
+        let second = tracker.diff(HashMap::from([
+            (1, socket(10, 150, 80)),
+            (2, socket(11, 5, 6)), //new socket, all traffic is recent
+        ]));
+        assert_eq!(second[&id(10)], (50, 30));
+        assert_eq!(second[&id(11)], (5, 6));
+        tracker.reset();
+        assert_eq!(
+            tracker.diff(HashMap::from([(2, socket(11, 9, 9))]))[&id(11)],
+            (0, 0)
+        );
+    }
+}
@@ -0,0 +1,81 @@
+use std::fs;
+
+///Values from /proc/<pid>/stat which sysinfo does not expose
+#[derive(Debug, Default, PartialEq)]
+pub struct ProcStat {
+    pub cutime: f64, //seconds of user cpu time of waited-for children
+    pub cstime: f64, //seconds of system cpu time of waited-for children
+}
+
+impl ProcStat {
+    ///cpu time of children which have exited and been reaped by this process
+    pub fn exited_children_cpu_time(&self) -> f64 {
+        self.cutime + self.cstime
+    }
+}
+
+#[cfg(target_os = "linux")]
+pub fn read(pid: usize) -> Option<ProcStat> {
+    parse(
+        &fs::read_to_string(format!("/proc/{pid}/stat")).ok()?,
+        ticks_per_sec(),
+    )
+}
+
+///only available on linux
+#[cfg(not(target_os = "linux"))]
+pub fn read(_pid: usize) -> Option<ProcStat> {
+    None
+}
+
+///clock ticks per second used in /proc/<pid>/stat (USER_HZ)
+#[cfg(target_os = "linux")]
+fn ticks_per_sec() -> f64 {
+    static TICKS: std::sync::OnceLock<f64> = std::sync::OnceLock::new();
+    *TICKS.get_or_init(|| match unsafe { libc::sysconf(libc::_SC_CLK_TCK) } {
+        ticks if ticks > 0 => ticks as f64,
+        _ => 100.,
+    })
+}
+
+///number of forks since boot from /proc/stat, this includes threads
+pub fn total_forks() -> Option<u64> {
+    fs::read_to_string("/proc/stat")
+        .ok()?
+        .lines()
+        .find_map(|line| line.strip_prefix("processes "))?
+        .trim()
+        .parse()
+        .ok()
+}
+
+#[cfg(any(target_os = "linux", test))]
+fn parse(contents: &str, ticks_per_sec: f64) -> Option<ProcStat> {
+    //name is in brackets and may contain spaces, so we start after the last bracket
+    let (_, rest) = contents.rsplit_once(')')?;
+    let fields: Vec<&str> = rest.split_whitespace().collect();
+    //fields after the name start at field 3 (state)
+    let field = |num: usize| -> Option<f64> { fields.get(num - 3)?.parse().ok() };
+    Some(ProcStat {
+        cutime: field(16)? / ticks_per_sec,
+        cstime: field(17)? / ticks_per_sec,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_stat() {
+        let stat = "7296 (my (odd) name) S 7292 7296 7292 0 -1 4194304 81 0 0 0 12 3 250 50 20 0 1 0 105859 2703360 313";
+        assert_eq!(
+            parse(stat, 100.),
+            Some(ProcStat {
+                cutime: 2.5,
+                cstime: 0.5,
+            })
+        );
+        assert_eq!(parse("garbage", 100.), None);
+    }
+}
@@ -0,0 +1,345 @@
+use regex::Regex;
+
+use crate::myprocess::{Metric, MyProcess};
+
+///Filter query, e.g. `mem>500M and (user:postgres or name~^pg)`
+///plain words match name and command, like the old filter
+#[derive(Debug, Clone)]
+pub enum Query {
+    All,
+    Text(String), //lowercase
+    Compare(NumField, Op, f64),
+    Contains(TextField, String), //lowercase
+    Equals(TextField, String),   //lowercase
+    Regex(TextField, Regex),
+    Not(Box<Query>),
+    And(Box<Query>, Box<Query>),
+    Or(Box<Query>, Box<Query>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NumField {
+    Pid,
+    Parent,
+    Memory,
+    Children,
+    Total,
+    Threads,
+    Metric(Metric),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TextField {
+    Name,
+    Command,
+    User,
+    State,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Op {
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    Equal,
+    NotEqual,
+    Contains, //:
+    Regex,    //~
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Open,
+    Close,
+    And,
+    Or,
+    Not,
+    Word(String),
+}
+
+impl Query {
+    pub fn parse(input: &str) -> Result<Query, String> {
+        let tokens = tokenize(input)?;
+        if tokens.is_empty() {
+            return Ok(Query::All);
+        }
+        let mut parser = Parser { tokens, pos: 0 };
+        let query = parser.or()?;
+        match parser.tokens.get(parser.pos) {
+            None => Ok(query),
+            Some(Token::Close) => Err("unexpected )".to_string()),
+            Some(token) => Err(format!("unexpected {token:?}")),
+        }
+    }
+
+    pub fn matches(&self, proc: &MyProcess) -> bool {
+        match self {
+            Query::All => true,
+            Query::Text(text) => {
+                proc.name.to_lowercase().contains(text)
+                    || proc.command_display.to_lowercase().contains(text)
+            }
+            Query::Compare(field, op, val) => {
+                let actual = field.value(proc);
+                match op {
+                    Op::Less => actual < *val,
+                    Op::LessEqual => actual <= *val,
+                    Op::Greater => actual > *val,
+                    Op::GreaterEqual => actual >= *val,
+                    Op::NotEqual => actual != *val,
+                    _ => actual == *val,
+                }
+            }
+            Query::Contains(field, text) => field.value(proc).to_lowercase().contains(text),
+            Query::Equals(field, text) => field.value(proc).to_lowercase() == *text,
+            Query::Regex(field, regex) => regex.is_match(field.value(proc)),
+            Query::Not(query) => !query.matches(proc),
+            Query::And(a, b) => a.matches(proc) && b.matches(proc),
+            Query::Or(a, b) => a.matches(proc) || b.matches(proc),
+        }
+    }
+}
+
+impl NumField {
+    fn from_name(name: &str) -> Option<NumField> {
+        let field = match name {
+            "pid" => NumField::Pid,
+            "ppid" | "parent" => NumField::Parent,
+            "mem" | "memory" => NumField::Memory,
+            "children" => NumField::Children,
+            "total" => NumField::Total,
+            "threads" => NumField::Threads,
+            _ => NumField::Metric(*Metric::ALL.iter().find(|m| m.key() == name)?),
+        };
+        Some(field)
+    }
+
+    fn value(&self, proc: &MyProcess) -> f64 {
+        match self {
+            NumField::Pid => proc.pid as f64,
+            NumField::Parent => proc.parent as f64,
+            NumField::Memory => proc.memory as f64,
+            NumField::Children => proc.children_memory as f64,
+            NumField::Total => proc.total() as f64,
+            NumField::Threads => proc.threads as f64,
+            NumField::Metric(metric) => proc.usage(*metric),
+        }
+    }
+}
+
+impl TextField {
+    fn from_name(name: &str) -> Option<TextField> {
+        match name {
+            "name" => Some(TextField::Name),
+            "cmd" | "command" => Some(TextField::Command),
+            "user" => Some(TextField::User),
+            "state" => Some(TextField::State),
+            _ => None,
+        }
+    }
+
+    fn value<'a>(&self, proc: &'a MyProcess) -> &'a str {
+        match self {
+            TextField::Name => &proc.name,
+            TextField::Command => &proc.command_display,
+            TextField::User => &proc.user,
+            TextField::State => proc.state.label(),
+        }
+    }
+}
+
+///parses a number with an optional size suffix, e.g. 500M or 1.5GB
+fn parse_number(val: &str) -> Option<f64> {
+    let lower = val.to_lowercase();
+    let lower = lower.trim_end_matches('%');
+    let lower = lower.strip_suffix('b').unwrap_or(lower);
+    let (num, multiplier) = match lower.chars().last()? {
+        'k' => (&lower[..lower.len() - 1], 1024.),
+        'm' => (&lower[..lower.len() - 1], 1024. * 1024.),
+        'g' => (&lower[..lower.len() - 1], 1024. * 1024. * 1024.),
+        't' => (&lower[..lower.len() - 1], 1024. * 1024. * 1024. * 1024.),
+        _ => (lower, 1.),
+    };
+    num.parse::<f64>().ok().map(|n| n * multiplier)
+}
+
+///a word is either a field predicate like `mem>10M`, or plain text
+fn parse_word(word: &str) -> Result<Query, String> {
+    const OPS: [(&str, Op); 8] = [
+        (">=", Op::GreaterEqual),
+        ("<=", Op::LessEqual),
+        ("!=", Op::NotEqual),
+        (">", Op::Greater),
+        ("<", Op::Less),
+        ("=", Op::Equal),
+        (":", Op::Contains),
+        ("~", Op::Regex),
+    ];
+    //the first operator in the word, longest first if they start at the same place
+    let found = OPS
+        .iter()
+        .filter_map(|(sym, op)| word.find(sym).map(|i| (i, *sym, *op)))
+        .min_by_key(|(i, sym, _)| (*i, std::cmp::Reverse(sym.len())));
+    let Some((i, sym, op)) = found else {
+        return Ok(Query::Text(word.to_lowercase()));
+    };
+    let name = word[..i].to_lowercase();
+    let value = &word[i + sym.len()..];
+
+    if let Some(field) = NumField::from_name(&name) {
+        let num = parse_number(value).ok_or(format!("{name} needs a number"))?;
+        return match op {
+            Op::Regex => Err(format!("{name} is a number, use < > = instead of ~")),
+            Op::Contains => Ok(Query::Compare(field, Op::Equal, num)),
+            _ => Ok(Query::Compare(field, op, num)),
+        };
+    }
+    if let Some(field) = TextField::from_name(&name) {
+        let lower = value.to_lowercase();
+        return match op {
+            Op::Contains => Ok(Query::Contains(field, lower)),
+            Op::Equal => Ok(Query::Equals(field, lower)),
+            Op::NotEqual => Ok(Query::Not(Box::new(Query::Equals(field, lower)))),
+            Op::Regex => Regex::new(&format!("(?i){value}"))
+                .map(|regex| Query::Regex(field, regex))
+                .map_err(|_| format!("invalid regex {value}")),
+            _ => Err(format!("{name} is text, use : = != or ~")),
+        };
+    }
+    //not a field, so could be part of a command like host:port
+    Ok(Query::Text(word.to_lowercase()))
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = vec![];
+    let mut chars = input.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '(' => {
+                chars.next();
+                tokens.push(Token::Open);
+            }
+            ')' => {
+                chars.next();
+                tokens.push(Token::Close);
+            }
+            '!' => {
+                chars.next();
+                tokens.push(Token::Not);
+            }
+            _ => {
+                //read word, quotes allow spaces and brackets
+                let mut word = String::new();
+                let mut quoted = false;
+                while let Some(&c) = chars.peek() {
+                    if c == '"' {
+                        quoted = !quoted;
+                    } else if !quoted && (c.is_whitespace() || c == '(' || c == ')') {
+                        break;
+                    } else {
+                        word.push(c);
+                    }
+                    chars.next();
+                }
+                if quoted {
+                    return Err("missing closing \"".to_string());
+                }
+                tokens.push(match word.to_lowercase().as_str() {
+                    "and" | "&&" => Token::And,
+                    "or" | "||" => Token::Or,
+                    "not" => Token::Not,
+                    _ => Token::Word(word),
+                });
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+///or has lowest precedence, then and (which can be left out), then not
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn or(&mut self) -> Result<Query, String> {
+        let mut query = self.and()?;
+        while self.tokens.get(self.pos) == Some(&Token::Or) {
+            self.pos += 1;
+            query = Query::Or(Box::new(query), Box::new(self.and()?));
+        }
+        Ok(query)
+    }
+
+    fn and(&mut self) -> Result<Query, String> {
+        let mut query = self.not()?;
+        loop {
+            match self.tokens.get(self.pos) {
+                Some(Token::And) => self.pos += 1,
+                Some(Token::Open | Token::Not | Token::Word(_)) => {}
+                _ => return Ok(query),
+            }
+            query = Query::And(Box::new(query), Box::new(self.not()?));
+        }
+    }
+
+    fn not(&mut self) -> Result<Query, String> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        match token {
+            Some(Token::Not) => Ok(Query::Not(Box::new(self.not()?))),
+            Some(Token::Open) => {
+                let query = self.or()?;
+                if self.tokens.get(self.pos) != Some(&Token::Close) {
+                    return Err("missing )".to_string());
+                }
+                self.pos += 1;
+                Ok(query)
+            }
+            Some(Token::Word(word)) => parse_word(&word),
+            Some(token) => Err(format!("unexpected {token:?}")),
+            None => Err("incomplete query".to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_query() {
+        let proc = MyProcess {
+            pid: 1234,
+            name: "postgres".to_string(),
+            command_display: "postgres -D /var/lib/postgres".to_string(),
+            user: "Postgres".to_string(),
+            memory: 600 * 1024 * 1024,
+            cpu: 12.5,
+            ..Default::default()
+        };
+        let matches = |query: &str| Query::parse(query).unwrap().matches(&proc);
+
+        assert!(matches(""));
+        assert!(matches("var/lib"));
+        assert!(matches("mem>500M"));
+        assert!(!matches("mem>1G"));
+        assert!(matches("cpu>=10 user:postgres"));
+        assert!(matches("pid:1234"));
+        assert!(matches("name~^post.*s$"));
+        assert!(matches("not name=bash and (pid=1 or mem>500mb)"));
+        assert!(!matches("!pid:1234 || cpu<1"));
+        assert!(matches(r#"cmd:"-D /var""#));
+        assert!(!matches("localhost:8080"));
+
+        assert!(Query::parse("mem>abc").is_err());
+        assert!(Query::parse("name~[").is_err());
+        assert!(Query::parse("(pid:1").is_err());
+        assert!(Query::parse("pid:1 or").is_err());
+    }
+}
@@ -0,0 +1,174 @@
+use std::{cmp::Reverse, collections::HashMap, fmt::Display};
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum MappingKind {
+    Heap,
+    Stack,
+    Anonymous,
+    SharedLibrary,
+    File,
+    Other, //kernel provided e.g. [vdso]
+}
+
+impl Display for MappingKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            MappingKind::Heap => "heap",
+            MappingKind::Stack => "stack",
+            MappingKind::Anonymous => "anon",
+            MappingKind::SharedLibrary => "library",
+            MappingKind::File => "file",
+            MappingKind::Other => "other",
+        };
+        write!(f, "{name}")
+    }
+}
+
+///Mappings of the same kind and backing file added together, all sizes in bytes
+#[derive(Clone, Debug)]
+pub struct MappingGroup {
+    pub kind: MappingKind,
+    pub name: String,
+    pub count: usize,
+    pub rss: u64,
+    pub pss: u64,
+    pub swap: u64,
+}
+
+///reads /proc/<pid>/smaps, only available on linux
+pub fn load(pid: usize) -> Result<Vec<MappingGroup>, String> {
+    std::fs::read_to_string(format!("/proc/{pid}/smaps"))
+        .map(|contents| parse(&contents))
+        .map_err(|e| format!("unable to read memory map of {pid}: {e}"))
+}
+
+///groups mappings by kind and name, sorted by rss descending
+pub fn parse(contents: &str) -> Vec<MappingGroup> {
+    let mut groups: HashMap<(MappingKind, String), MappingGroup> = HashMap::new();
+    let mut current: Option<(MappingKind, String)> = None;
+
+    for line in contents.lines() {
+        let mut parts = line.split_whitespace();
+        let Some(first) = parts.next() else {
+            continue;
+        };
+        if let Some(field) = first.strip_suffix(':') {
+            //field of current mapping e.g. "Rss:   8 kB"
+            let Some(group) = current.as_ref().and_then(|key| groups.get_mut(key)) else {
+                continue;
+            };
+            let bytes = parts
+                .next()
+                .and_then(|v| v.parse::<u64>().ok())
+                .unwrap_or_default()
+                * 1024;
+            match field {
+                "Rss" => group.rss += bytes,
+                "Pss" => group.pss += bytes,
+                "Swap" => group.swap += bytes,
+                _ => {}
+            }
+        } else if first.contains('-') {
+            //header: address perms offset dev inode [pathname]
+            //path may contain spaces, so take everything after the inode column
+            let path = parts.skip(4).collect::<Vec<_>>().join(" ");
+            let (kind, name) = classify(&path);
+            let group = groups
+                .entry((kind, name.clone()))
+                .or_insert_with(|| MappingGroup {
+                    kind,
+                    name,
+                    count: 0,
+                    rss: 0,
+                    pss: 0,
+                    swap: 0,
+                });
+            group.count += 1;
+            current = Some((kind, group.name.clone()));
+        }
+    }
+
+    let mut groups: Vec<MappingGroup> = groups.into_values().collect();
+    groups.sort_by_key(|g| (Reverse(g.rss), Reverse(g.swap), g.name.clone()));
+    groups
+}
+
+fn classify(path: &str) -> (MappingKind, String) {
+    let kind = match path {
+        "" => MappingKind::Anonymous,
+        "[heap]" => MappingKind::Heap,
+        p if p.starts_with("[stack") => MappingKind::Stack,
+        p if p.starts_with("[anon") => MappingKind::Anonymous,
+        p if p.starts_with('[') => MappingKind::Other,
+        p if p.ends_with(".so") || p.contains(".so.") => MappingKind::SharedLibrary,
+        _ => MappingKind::File,
+    };
+    let name = if path.is_empty() {
+        "[anon]".to_string()
+    } else {
+        path.to_string()
+    };
+    (kind, name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_smaps() {
+        let contents = "\
+55d0-55d2 r--p 00000000 fe:00 317783                     /usr/bin/my app
+Rss:                   8 kB
+Pss:                   4 kB
+Swap:                  0 kB
+55d2-55d4 r-xp 00002000 fe:00 317783                     /usr/bin/my app
+Rss:                  24 kB
+Pss:                  24 kB
+Swap:                  0 kB
+55d4-55d6 rw-p 00000000 00:00 0                          [heap]
+Rss:                 100 kB
+Pss:                 100 kB
+Swap:                 50 kB
+7f00-7f02 rw-p 00000000 00:00 0
+Rss:                  10 kB
+Pss:                  10 kB
+Swap:                  2 kB
+7f02-7f04 r-xp 00000000 fe:00 1234                       /usr/lib/libc.so.6
+Rss:                  40 kB
+Pss:                   5 kB
+Swap:                  0 kB
+VmFlags: rd ex mr mw me
+";
+        let groups = parse(contents);
+        let summary: Vec<_> = groups
+            .iter()
+            .map(|g| {
+                (
+                    g.kind,
+                    g.name.as_str(),
+                    g.count,
+                    g.rss / 1024,
+                    g.pss / 1024,
+                    g.swap / 1024,
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (MappingKind::Heap, "[heap]", 1, 100, 100, 50),
+                (
+                    MappingKind::SharedLibrary,
+                    "/usr/lib/libc.so.6",
+                    1,
+                    40,
+                    5,
+                    0
+                ),
+                (MappingKind::File, "/usr/bin/my app", 2, 32, 28, 0),
+                (MappingKind::Anonymous, "[anon]", 1, 10, 10, 2),
+            ]
+        );
+    }
+}
@@ -0,0 +1,187 @@
+use std::{
+    cmp::Reverse,
+    collections::{HashMap, HashSet},
+    time::SystemTime,
+};
+
+use crate::{manager::Totals, mynetwork::MyNetwork, myprocess::MyProcess};
+
+///Processes and system totals at one point in time, owned so it can be kept between updates
+#[derive(Clone, Debug)]
+pub struct Snapshot {
+    procs: Vec<MyProcess>, //children_memory and children_usage include all descendants
+    totals: Totals,
+    networks: Vec<MyNetwork>,
+    taken: SystemTime,
+    index: HashMap<usize, usize>,         //pid to position in procs
+    children: HashMap<usize, Vec<usize>>, //pid to positions of its children
+}
+
+impl Snapshot {
+    pub(crate) fn new(procs: Vec<MyProcess>, totals: Totals, networks: Vec<MyNetwork>) -> Self {
+        let index: HashMap<usize, usize> =
+            procs.iter().enumerate().map(|(i, p)| (p.pid, i)).collect();
+        let mut children: HashMap<usize, Vec<usize>> = HashMap::new();
+        for (i, proc) in procs.iter().enumerate() {
+            //roots can be their own parent, which would loop
+            if proc.parent != proc.pid {
+                children.entry(proc.parent).or_default().push(i);
+            }
+        }
+        Snapshot {
+            procs,
+            totals,
+            networks,
+            taken: SystemTime::now(),
+            index,
+            children,
+        }
+    }
+
+    ///snapshot of processes only, totals are left empty
+    pub fn from_procs(procs: Vec<MyProcess>) -> Self {
+        Snapshot::new(procs, Totals::default(), vec![])
+    }
+
+    ///all processes, in no particular order
+    pub fn procs(&self) -> &[MyProcess] {
+        &self.procs
+    }
+
+    ///system usage when the snapshot was taken
+    pub fn totals(&self) -> &Totals {
+        &self.totals
+    }
+
+    ///network interfaces when the snapshot was taken
+    pub fn networks(&self) -> &[MyNetwork] {
+        &self.networks
+    }
+
+    ///when the snapshot was taken
+    pub fn taken(&self) -> SystemTime {
+        self.taken
+    }
+
+    ///process with this pid, if any
+    pub fn get(&self, pid: usize) -> Option<&MyProcess> {
+        self.index.get(&pid).map(|i| &self.procs[*i])
+    }
+
+    ///processes without a known parent, usually just init
+    pub fn roots(&self) -> impl Iterator<Item = &MyProcess> {
+        self.procs
+            .iter()
+            .filter(|p| p.parent == p.pid || !self.index.contains_key(&p.parent))
+    }
+
+    ///direct children, in no particular order
+    pub fn children(&self, pid: usize) -> impl Iterator<Item = &MyProcess> {
+        self.children
+            .get(&pid)
+            .into_iter()
+            .flatten()
+            .map(|i| &self.procs[*i])
+    }
+
+    ///parent first, up to the root
+    pub fn ancestors(&self, pid: usize) -> Vec<&MyProcess> {
+        let mut result: Vec<&MyProcess> = vec![];
+        let mut seen = HashSet::from([pid]);
+        let mut parent = self.get(pid).and_then(|p| self.get(p.parent));
+        while let Some(p) = parent
+            && seen.insert(p.pid)
+        {
+            result.push(p);
+            parent = self.get(p.parent);
+        }
+        result
+    }
+
+    ///all processes below pid, closest first
+    pub fn descendants(&self, pid: usize) -> Vec<&MyProcess> {
+        //a cycle of parents would repeat forever
+        let mut seen = HashSet::from([pid]);
+        let mut result: Vec<&MyProcess> =
+            self.children(pid).filter(|c| seen.insert(c.pid)).collect();
+        let mut i = 0;
+        while i < result.len() {
+            let parent = result[i].pid;
+            result.extend(self.children(parent).filter(|c| seen.insert(c.pid)));
+            i += 1;
+        }
+        result
+    }
+
+    ///depth first over the whole tree, siblings ordered by total memory
+    ///yields the depth, which is 1 for roots like [`MyProcess::depth`]
+    pub fn walk(&self) -> Walk<'_> {
+        let mut children: HashMap<usize, Vec<&MyProcess>> = self
+            .children
+            .iter()
+            .map(|(pid, c)| (*pid, c.iter().map(|i| &self.procs[*i]).collect()))
+            .collect();
+        for siblings in children.values_mut() {
+            siblings.sort_by_key(|p| Reverse(p.total()));
+        }
+        let mut stack: Vec<(usize, &MyProcess)> = self.roots().map(|p| (1, p)).collect();
+        stack.sort_by_key(|(_, p)| p.total());
+        Walk { children, stack }
+    }
+}
+
+///Iterator returned by [`Snapshot::walk`], yields the depth and the process
+pub struct Walk<'a> {
+    children: HashMap<usize, Vec<&'a MyProcess>>,
+    stack: Vec<(usize, &'a MyProcess)>, //next is last
+}
+
+impl<'a> Iterator for Walk<'a> {
+    type Item = (usize, &'a MyProcess);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (depth, proc) = self.stack.pop()?;
+        //removed so a cycle of parents is only walked once
+        if let Some(children) = self.children.remove(&proc.pid) {
+            self.stack
+                .extend(children.into_iter().rev().map(|c| (depth + 1, c)));
+        }
+        Some((depth, proc))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tree() {
+        let proc = |pid, parent, memory| MyProcess {
+            pid,
+            parent,
+            memory,
+            ..Default::default()
+        };
+        //1 has children 2 and 5, 2 has 3 which has 4
+        let snapshot = Snapshot::from_procs(vec![
+            proc(1, 0, 10),
+            proc(2, 1, 1),
+            proc(3, 2, 1),
+            proc(4, 3, 1),
+            proc(5, 1, 20),
+        ]);
+        let pids = |procs: Vec<&MyProcess>| procs.iter().map(|p| p.pid).collect::<Vec<_>>();
+        assert_eq!(pids(snapshot.roots().collect()), vec![1]);
+        assert_eq!(pids(snapshot.ancestors(4)), vec![3, 2, 1]);
+        assert_eq!(pids(snapshot.descendants(2)), vec![3, 4]);
+        let walk: Vec<(usize, usize)> = snapshot.walk().map(|(d, p)| (d, p.pid)).collect();
+        assert_eq!(walk, vec![(1, 1), (2, 5), (2, 2), (3, 3), (4, 4)]);
+        assert_eq!(snapshot.get(3).map(|p| p.parent), Some(2));
+        assert!(snapshot.get(6).is_none());
+
+        //8 and 9 are each other's parent
+        let snapshot = Snapshot::from_procs(vec![proc(1, 0, 1), proc(8, 9, 1), proc(9, 8, 1)]);
+        assert_eq!(pids(snapshot.descendants(8)), vec![9]);
+        assert_eq!(pids(snapshot.ancestors(8)), vec![9]);
+    }
+}
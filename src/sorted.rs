@@ -1,136 +1,411 @@
-use crate::{helpers::nice_size, myprocess::MyProcess};
+use crate::{
+    columns::Column,
+    helpers::nice_size,
+    myprocess::{Metric, MyProcess, ProcId, ProcState},
+    query::Query,
+};
 use serde::{Deserialize, Serialize};
-use std::{cmp::Reverse, collections::HashSet};
+use std::collections::{HashMap, HashSet};
 
-#[derive(PartialEq, Debug, Serialize, Deserialize, Clone, Copy)]
+///Direction of the sort, None keeps the tree order
+#[derive(PartialEq, Debug, Serialize, Deserialize, Clone, Copy, Default)]
 pub enum SortType {
     Ascending,
     Descending,
+    #[default]
     None,
 }
+
+///Saved filter, sort and collapse state, shared by tui and gui
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct View {
+    ///shown in the views list
+    pub name: String,
+    ///filter text, see [`SortedProcesses::set_filter`]
+    #[serde(default)]
+    pub filter: String,
+    ///column id, see [`Column::id`]
+    #[serde(default, deserialize_with = "Column::deserialize_id")]
+    pub sort_column: String,
+    ///empty keeps the current columns
+    #[serde(default)]
+    pub columns: Vec<String>,
+    ///see [`SortedProcesses::sort_type`]
+    #[serde(default)]
+    pub sort_type: SortType,
+    ///see [`SortedProcesses::tree_mode`]
+    #[serde(default)]
+    pub tree_mode: bool,
+    ///see [`SortedProcesses::tree_totals`]
+    #[serde(default)]
+    pub tree_totals: bool,
+    ///see [`SortedProcesses::keep_ancestors`]
+    #[serde(default)]
+    pub keep_ancestors: bool,
+    ///only applies while these processes are running
+    #[serde(default)]
+    pub collapsed: Vec<ProcId>,
+    ///see [`SortedProcesses::auto_collapse`]
+    #[serde(default)]
+    pub auto_collapse: Option<u64>,
+    ///empty keeps the current setting
+    #[serde(default)]
+    pub hidezeros: Option<bool>,
+    ///see [`SortedProcesses::state_filter`]
+    #[serde(default)]
+    pub state_filter: Option<ProcState>,
+    ///empty keeps the current setting
+    #[serde(default)]
+    pub hide_kernel_threads: Option<bool>,
+}
+
+///A visible process, columns are formatted when rendering
+#[non_exhaustive]
+pub struct ProcRow {
+    ///raw values, proc.depth is the depth in the tree
+    pub proc: MyProcess,
+    ///tree lines before the command, only in tree view
+    pub prefix: String,
+    ///if not 0, this summarises this many small processes, and proc is not real
+    pub hidden: usize,
+}
+
+///Filtered and sorted processes, optionally as a tree, as shown in the process table
 pub struct SortedProcesses {
-    pub sort_col: usize,
+    ///visible columns in order
+    pub columns: Vec<Column>,
+    ///sorted column, ignored while sort_type is None
+    pub sort_col: Column,
+    ///direction, None keeps the tree order
     pub sort_type: SortType,
+    ///keep the tree when sorting, siblings are ordered by sort_col
+    pub tree_mode: bool,
+    ///hide processes without memory, except zombies
     pub hidezeros: bool,
+    ///last filter text, use set_filter to change it
     pub filter: String,
-    pub hide_children: HashSet<usize>, //hide children of this process(used for tree view only)
+    ///show ancestors of filtered processes in tree view
+    pub keep_ancestors: bool,
+    ///only show processes in this state
+    pub state_filter: Option<ProcState>,
+    ///only detected on linux
+    pub hide_kernel_threads: bool,
+    ///hide children of this process(used for tree view only)
+    pub hide_children: HashSet<ProcId>,
+    ///show metrics including all descendants
+    pub tree_totals: bool,
+    ///group subtrees with less total memory in tree view
+    pub auto_collapse: Option<u64>,
+    ///only show this process, its ancestors and descendants
+    pub family: Option<ProcId>,
+    query: Query,
+    filter_error: Option<String>,
+    context: HashSet<ProcId>, //ancestors which are only shown because of keep_ancestors
     procs: Vec<MyProcess>,
-    cached: Vec<Vec<String>>, //cached output
+    tree: HashMap<ProcId, String>, //prefix of each process in tree view
+    rows: Vec<ProcRow>,
+}
+
+impl Default for SortedProcesses {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl SortedProcesses {
+    ///default columns in tree order, call update to add processes
     pub fn new() -> Self {
         Self {
-            sort_col: 0,
+            columns: Column::DEFAULT.to_vec(),
+            sort_col: Column::Command,
             sort_type: SortType::None,
+            tree_mode: false,
             procs: vec![],
             hidezeros: true,
             filter: String::new(),
+            keep_ancestors: false,
+            query: Query::All,
+            filter_error: None,
+            context: Default::default(),
+            state_filter: None,
+            hide_kernel_threads: true,
             hide_children: Default::default(),
-            cached: vec![],
+            tree_totals: false,
+            auto_collapse: None,
+            family: None,
+            tree: HashMap::new(),
+            rows: vec![],
         }
     }
 
+    ///an invalid query falls back to matching the text, and the error is kept for display
     pub fn set_filter(&mut self, str: String) {
+        (self.query, self.filter_error) = match Query::parse(&str) {
+            Ok(query) => (query, None),
+            Err(err) => (Query::Text(str.to_lowercase()), Some(err)),
+        };
         self.filter = str;
     }
-    pub fn hide_children_invert(&mut self, pid: usize) {
-        if self.hide_children.contains(&pid) {
-            self.hide_children.remove(&pid);
+    ///why the filter is not a valid query
+    pub fn filter_error(&self) -> Option<&String> {
+        self.filter_error.as_ref()
+    }
+    ///if the process is not matched, but shown as ancestor of a match
+    pub fn is_context(&self, id: &ProcId) -> bool {
+        self.context.contains(id)
+    }
+    ///call calc to show the result
+    pub fn apply_view(&mut self, view: &View) {
+        self.set_filter(view.filter.clone());
+        self.sort_col = Column::from_id(&view.sort_column).unwrap_or(Column::Command);
+        if !view.columns.is_empty() {
+            self.columns = Column::from_ids(&view.columns);
+        }
+        self.sort_type = view.sort_type;
+        self.tree_mode = view.tree_mode;
+        self.tree_totals = view.tree_totals;
+        self.keep_ancestors = view.keep_ancestors;
+        self.hide_children = view.collapsed.iter().copied().collect();
+        self.auto_collapse = view.auto_collapse;
+        self.hidezeros = view.hidezeros.unwrap_or(self.hidezeros);
+        self.state_filter = view.state_filter;
+        self.hide_kernel_threads = view.hide_kernel_threads.unwrap_or(self.hide_kernel_threads);
+    }
+    ///metrics of the visible and sorted columns, see [`crate::ProcManager::set_tracked`]
+    pub fn metrics(&self) -> Vec<Metric> {
+        let mut metrics: Vec<Metric> = self.columns.iter().filter_map(|c| c.metric()).collect();
+        if self.sort_type != SortType::None {
+            metrics.extend(self.sort_col.metric());
+        }
+        metrics
+    }
+
+    ///current state, to save as a view
+    pub fn to_view(&self, name: String) -> View {
+        View {
+            name,
+            filter: self.filter.clone(),
+            sort_column: self.sort_col.id(),
+            columns: self.columns.iter().map(|c| c.id()).collect(),
+            sort_type: self.sort_type,
+            tree_mode: self.tree_mode,
+            tree_totals: self.tree_totals,
+            keep_ancestors: self.keep_ancestors,
+            collapsed: self.hide_children.iter().copied().collect(),
+            auto_collapse: self.auto_collapse,
+            hidezeros: Some(self.hidezeros),
+            state_filter: self.state_filter,
+            hide_kernel_threads: Some(self.hide_kernel_threads),
+        }
+    }
+    ///processes are shown as a tree, either the default order or tree_mode
+    ///the tree is drawn in the command column, so without it the list is flat
+    pub fn is_tree(&self) -> bool {
+        (self.tree_mode || self.sort_type == SortType::None)
+            && self.columns.contains(&Column::Command)
+    }
+    ///collapse or expand the process, call calc to show the result
+    pub fn hide_children_invert(&mut self, id: ProcId) {
+        if self.hide_children.contains(&id) {
+            self.hide_children.remove(&id);
         } else {
-            self.hide_children.insert(pid);
+            self.hide_children.insert(id);
+        }
+    }
+
+    ///collapse every process deeper than depth, so depth levels are visible
+    pub fn collapse_to_depth(&mut self, depth: usize) {
+        let parents: HashSet<ProcId> = self.procs.iter().map(|p| p.parent_id()).collect();
+        self.hide_children = self
+            .procs
+            .iter()
+            .filter(|p| p.depth >= depth && parents.contains(&p.id()))
+            .map(|p| p.id())
+            .collect();
+        self.calc();
+    }
+    ///only roots are visible
+    pub fn collapse_all(&mut self) {
+        self.collapse_to_depth(1);
+    }
+    ///every process is visible
+    pub fn expand_all(&mut self) {
+        self.hide_children.clear();
+        self.calc();
+    }
+    ///deepest level currently visible
+    pub fn visible_depth(&self) -> usize {
+        self.rows.iter().map(|r| r.proc.depth).max().unwrap_or(1)
+    }
+    ///collapse everything except the ancestors of the process
+    pub fn expand_to(&mut self, id: ProcId) {
+        let by_id: HashMap<ProcId, &MyProcess> = self.procs.iter().map(|p| (p.id(), p)).collect();
+        let mut path = HashSet::new();
+        let mut parent = by_id.get(&id).map(|p| p.parent_id());
+        while let Some(id) = parent
+            && path.insert(id)
+        {
+            parent = by_id.get(&id).map(|p| p.parent_id());
         }
+        let parents: HashSet<ProcId> = self.procs.iter().map(|p| p.parent_id()).collect();
+        self.hide_children = self
+            .procs
+            .iter()
+            .filter(|p| parents.contains(&p.id()) && !path.contains(&p.id()))
+            .map(|p| p.id())
+            .collect();
+        self.calc();
     }
 
+    ///parent first, up to the root, empty if the process exited
+    pub fn ancestors(&self, id: &ProcId) -> Vec<&MyProcess> {
+        let by_id: HashMap<ProcId, &MyProcess> = self.procs.iter().map(|p| (p.id(), p)).collect();
+        let mut result: Vec<&MyProcess> = vec![];
+        let mut parent = by_id.get(id).and_then(|p| by_id.get(&p.parent_id()));
+        while let Some(p) = parent
+            && p.id() != *id
+            && !result.iter().any(|a| a.id() == p.id())
+        {
+            result.push(p);
+            parent = by_id.get(&p.parent_id());
+        }
+        result
+    }
+    ///the process, its ancestors and descendants, empty if it exited
+    fn family_ids(&self, id: &ProcId) -> HashSet<ProcId> {
+        if !self.procs.iter().any(|p| p.id() == *id) {
+            return Default::default();
+        }
+        let procs: Vec<&MyProcess> = self.procs.iter().collect();
+        let mut ids = recurse_children(&procs, &HashSet::from([*id]));
+        ids.insert(*id);
+        ids.extend(self.ancestors(id).iter().map(|p| p.id()));
+        ids
+    }
+
+    ///replaces the processes, then sorts and filters them
     pub fn update(&mut self, procs: &[MyProcess]) {
         self.procs = procs.to_vec();
         self.sort();
         self.calc();
     }
-    pub fn procs(&self) -> &Vec<Vec<String>> {
-        &self.cached
+    ///visible rows in display order
+    pub fn rows(&self) -> &Vec<ProcRow> {
+        &self.rows
+    }
+    ///row of the process
+    pub fn position(&self, id: &ProcId) -> Option<usize> {
+        self.rows.iter().position(|r| r.proc.id() == *id)
+    }
+    ///text of the cell, the command includes the tree prefix
+    pub fn format(&self, row: &ProcRow, col: Column) -> String {
+        if row.hidden > 0 {
+            return match col {
+                Column::Command => format!(
+                    "{}+{} more ({})",
+                    row.prefix,
+                    row.hidden,
+                    nice_size(row.proc.total())
+                ),
+                Column::Total => nice_size(row.proc.total()),
+                _ => String::new(),
+            };
+        }
+        match col {
+            Column::Command => format!("{}{}", row.prefix, row.proc.command_display),
+            _ => col.format(&row.proc, self.tree_totals),
+        }
     }
+    ///filters and collapses again, the sort only changes on update
     pub fn calc(&mut self) {
+        let family = self.family.map(|id| self.family_ids(&id));
         let mut procs = self
             .procs
             .iter()
-            .filter(|f| if self.hidezeros { f.memory != 0 } else { true })
-            .filter(|x| {
-                if self.filter.is_empty() {
-                    true
-                } else {
-                    x.name.to_lowercase().contains(&self.filter.to_lowercase())
-                        || x.command_display
-                            .to_lowercase()
-                            .contains(&self.filter.to_lowercase())
-                }
-            })
+            .filter(|f| family.as_ref().is_none_or(|ids| ids.contains(&f.id())))
+            //zombies have no memory, but are still of interest
+            .filter(|f| !self.hidezeros || f.memory != 0 || f.state == ProcState::Zombie)
+            .filter(|f| !(self.hide_kernel_threads && f.kernel_thread))
+            .filter(|f| self.state_filter.is_none_or(|state| f.state == state))
+            .filter(|x| self.query.matches(x))
             .collect::<Vec<_>>();
+
+        //add ancestors of matches, procs are already in tree order so we just keep those
+        self.context.clear();
+        let filtering = !matches!(self.query, Query::All) || self.state_filter.is_some();
+        if self.keep_ancestors && self.is_tree() && filtering {
+            let parents: HashMap<ProcId, &MyProcess> =
+                self.procs.iter().map(|p| (p.id(), p)).collect();
+            let mut keep: HashSet<ProcId> = procs.iter().map(|p| p.id()).collect();
+            for proc in &procs {
+                let mut parent = parents.get(&proc.parent_id());
+                while let Some(p) = parent
+                    && keep.insert(p.id())
+                {
+                    self.context.insert(p.id());
+                    parent = parents.get(&p.parent_id());
+                }
+            }
+            procs = self
+                .procs
+                .iter()
+                .filter(|p| keep.contains(&p.id()))
+                .collect();
+        }
         //now remove all the children of the minimized pids, only if tree view
-        if self.sort_col == 0 {
-            let pids_to_remove: HashSet<usize> = recurse_children(&procs, &self.hide_children);
-            procs.retain(|a| !pids_to_remove.contains(&a.pid));
+        if self.is_tree() {
+            let hidden: HashSet<ProcId> = procs
+                .iter()
+                .map(|p| p.id())
+                .filter(|id| self.hide_children.contains(id))
+                .collect();
+            let to_remove = recurse_children(&procs, &hidden);
+            procs.retain(|a| !to_remove.contains(&a.id()));
         }
 
-        let cache = procs
-            .iter()
-            .map(|f| {
-                vec![
-                    f.command_display.clone(),
-                    f.name.clone(),
-                    f.pid.to_string(),
-                    nice_size(f.memory),
-                    nice_size(f.children_memory),
-                    nice_size(f.total()),
-                    //cpu
-                    if f.cpu == 0. {
-                        String::new()
-                    } else {
-                        format!("{:.1}%", f.cpu)
-                    },
-                    //disk
-                    if f.disk == 0. {
-                        String::new()
-                    } else {
-                        format!("{}/s", nice_size(f.disk as u64))
-                    },
-                ]
+        self.rows = procs
+            .into_iter()
+            .map(|p| {
+                let prefix = self.tree.get(&p.id()).cloned().unwrap_or_default();
+                ProcRow {
+                    proc: p.clone(),
+                    prefix,
+                    hidden: 0,
+                }
             })
             .collect();
-        self.cached = cache;
+
+        if let Some(threshold) = self.auto_collapse
+            && self.is_tree()
+        {
+            self.rows = summarise_small(std::mem::take(&mut self.rows), threshold);
+        }
     }
 
     fn sort(&mut self) {
-        match self.sort_col {
-            0 => self
-                .procs
-                .sort_by_key(|a| Reverse(a.command_display.to_lowercase())),
-            1 => self.procs.sort_by_key(|a| Reverse(a.name.to_lowercase())),
-            2 => self.procs.sort_by_key(|a| Reverse(a.pid)),
-            3 => self.procs.sort_by_key(|a| Reverse(a.memory)),
-            4 => self.procs.sort_by_key(|a| Reverse(a.children_memory)),
-            5 => self.procs.sort_by_key(|a| Reverse(a.total())),
-            6 => self.procs.sort_by(|a, b| b.cpu.total_cmp(&a.cpu)),
-            7 => self.procs.sort_by(|a, b| b.disk.total_cmp(&a.disk)),
-            _ => unreachable!(),
-        }
-        if self.sort_type == SortType::Ascending {
-            self.procs.reverse();
+        let (col, tree_totals) = (self.sort_col, self.tree_totals);
+        if self.sort_type == SortType::None {
+            //default tree is sorted by memtotal
+            self.procs.sort_by_key(|a| std::cmp::Reverse(a.total()));
+        } else {
+            self.procs
+                .sort_by(|a, b| col.compare(a, b, tree_totals).reverse());
+            if self.sort_type == SortType::Ascending {
+                self.procs.reverse();
+            }
         }
 
-        if self.sort_type == SortType::None {
-            Self::sort_by_command_with_tree(&mut self.procs);
+        self.tree.clear();
+        if self.is_tree() {
+            self.build_tree();
         }
     }
 
-    fn sort_by_command_with_tree(procs: &mut Vec<MyProcess>) {
-        procs.sort_by_key(|a| std::cmp::Reverse(a.total()));
-        //get procs, and list of their children, sort by memtotal
-        //this should put tree in order
-        let ordered = children_of(0, procs, 0);
-        if ordered.is_empty() {
-            return;
-        }
+    ///orders procs as a tree, siblings keep the order they are sorted in
+    fn build_tree(&mut self) {
+        let procs = &mut self.procs;
+        //index of procs and their level, in tree order
+        let ordered = tree_order(procs);
 
         //difficult part is determining look of the tree:
         // if it has a sibling before, or parent it needs up
@@ -138,15 +413,11 @@ impl SortedProcesses {
         let mut result = vec![];
 
         let mut later_siblings = HashSet::new();
-        for i in 0..ordered.len() - 1 {
-            let mut child = procs
-                .iter()
-                .find(|f| f.pid == ordered[i].0)
-                .unwrap()
-                .clone();
+        for i in 0..ordered.len() {
+            let child = procs[ordered[i].0].clone();
 
             let mut has = false;
-            for j in i + 1..ordered.len() - 1 {
+            for j in i + 1..ordered.len() {
                 //if has sibling
                 if ordered[j].1 == ordered[i].1 {
                     has = true;
@@ -172,24 +443,25 @@ impl SortedProcesses {
                     } else {
                         sym.push('│');
                     }
-                } else if !later_siblings.contains(&i) && j == ordered[i].1 {
+                } else if j == ordered[i].1 {
                     sym.push('└');
                 } else {
                     sym.push(' ');
                 }
             }
 
-            child.command_display = format!("{sym}{}", child.command_display);
+            self.tree.insert(child.id(), sym);
             result.push(child);
         }
         *procs = result;
     }
 
+    ///next sort type when the sorted column is clicked again
     pub fn sort_cycle(&mut self) {
         match (self.sort_col, &self.sort_type) {
-            (0, SortType::None) => self.sort_type = SortType::Ascending,
-            (0, SortType::Ascending) => self.sort_type = SortType::Descending,
-            (0, SortType::Descending) => self.sort_type = SortType::None,
+            (Column::Command, SortType::None) => self.sort_type = SortType::Ascending,
+            (Column::Command, SortType::Ascending) => self.sort_type = SortType::Descending,
+            (Column::Command, SortType::Descending) => self.sort_type = SortType::None,
             (_, SortType::None) => self.sort_type = SortType::Descending,
             (_, SortType::Ascending) => self.sort_type = SortType::Descending,
             (_, SortType::Descending) => self.sort_type = SortType::Ascending,
@@ -197,32 +469,349 @@ impl SortedProcesses {
     }
 }
 
-fn recurse_children(procs: &[&MyProcess], parents: &HashSet<usize>) -> HashSet<usize> {
-    if parents.is_empty() {
-        return Default::default();
+///replaces subtrees with less total memory than threshold with one row per parent
+///the summary is added after the last sibling, as siblings can be sorted by any column
+fn summarise_small(rows: Vec<ProcRow>, threshold: u64) -> Vec<ProcRow> {
+    let mut result: Vec<ProcRow> = vec![];
+    let mut small: HashSet<ProcId> = HashSet::new();
+    let mut pending: Vec<ProcRow> = vec![]; //summaries of the parents we are in
+    for row in rows {
+        let proc = &row.proc;
+        if small.contains(&proc.parent_id()) {
+            //descendant of a small process, already included in its total
+            small.insert(proc.id());
+            if let Some(summary) = pending.last_mut() {
+                summary.hidden += 1;
+            }
+            continue;
+        }
+        //siblings of the summary have ended
+        while let Some(summary) = pending.pop_if(|s| {
+            proc.depth < s.proc.depth
+                || (proc.depth == s.proc.depth && proc.parent_id() != s.proc.parent_id())
+        }) {
+            push_summary(&mut result, summary);
+        }
+        if proc.total() < threshold {
+            small.insert(proc.id());
+            match pending.last_mut() {
+                Some(summary) if summary.proc.parent_id() == proc.parent_id() => {
+                    summary.hidden += 1;
+                    summary.proc.children_memory += proc.total();
+                }
+                _ => {
+                    //summary is the last sibling
+                    let mut prefix = row.prefix.clone();
+                    if prefix.pop().is_some() {
+                        prefix.push('└');
+                    }
+                    pending.push(ProcRow {
+                        proc: MyProcess {
+                            parent: proc.parent,
+                            parent_start_time: proc.parent_start_time,
+                            depth: proc.depth,
+                            children_memory: proc.total(),
+                            ..Default::default()
+                        },
+                        prefix,
+                        hidden: 1,
+                    });
+                }
+            }
+        } else {
+            result.push(row);
+        }
+    }
+    while let Some(summary) = pending.pop() {
+        push_summary(&mut result, summary);
     }
-    let mut children: HashSet<usize> = HashSet::new();
-    //remove parents
-    for parent in parents {
-        for proc in procs {
-            if &proc.parent == parent {
-                children.insert(proc.pid);
+    result
+}
+
+///adds the summary after the last real sibling, which then needs lines down to the summary
+fn push_summary(result: &mut Vec<ProcRow>, summary: ProcRow) {
+    if let Some(col) = summary.prefix.chars().count().checked_sub(1) {
+        let set = |row: &mut ProcRow, from: char, to: char| {
+            let mut chars: Vec<char> = row.prefix.chars().collect();
+            if chars.get(col) == Some(&from) {
+                chars[col] = to;
+                row.prefix = chars.into_iter().collect();
+            }
+        };
+        //rows deeper than the summary are descendants of the last sibling
+        for row in result.iter_mut().rev() {
+            if row.proc.depth > summary.proc.depth {
+                set(row, ' ', '│');
+            } else {
+                if row.proc.depth == summary.proc.depth
+                    && row.proc.parent_id() == summary.proc.parent_id()
+                {
+                    set(row, '└', '├');
+                }
+                break;
             }
         }
     }
-    children = children
-        .union(&recurse_children(procs, &children))
-        .cloned()
-        .collect();
+    result.push(summary);
+}
+
+///all descendants of the parents, each is only visited once so parent loops end
+fn recurse_children(procs: &[&MyProcess], parents: &HashSet<ProcId>) -> HashSet<ProcId> {
+    let mut children: HashSet<ProcId> = HashSet::new();
+    let mut current = parents.clone();
+    while !current.is_empty() {
+        current = procs
+            .iter()
+            .filter(|p| current.contains(&p.parent_id()))
+            .map(|p| p.id())
+            .filter(|id| children.insert(*id))
+            .collect();
+    }
     children
 }
 
-///this returns the children of given, their level
-fn children_of(pid: usize, procs: &Vec<MyProcess>, level: usize) -> Vec<(usize, usize)> {
-    let mut vec = vec![];
-    for p in procs.iter().filter(|f| f.parent == pid) {
-        vec.push((p.pid, level));
-        vec.extend(children_of(p.pid, procs, level + 1))
+///index of each process and its level in tree order, siblings keep their order
+///processes without a known parent are roots, and each process is visited once so parent loops end
+fn tree_order(procs: &[MyProcess]) -> Vec<(usize, usize)> {
+    let ids: HashSet<ProcId> = procs.iter().map(|p| p.id()).collect();
+    let mut children: HashMap<ProcId, Vec<usize>> = HashMap::new();
+    let mut roots = vec![];
+    for (i, proc) in procs.iter().enumerate() {
+        let parent = proc.parent_id();
+        if proc.parent != 0 && parent != proc.id() && ids.contains(&parent) {
+            children.entry(parent).or_default().push(i);
+        } else {
+            roots.push(i);
+        }
+    }
+    let mut result = vec![];
+    let mut stack: Vec<(usize, usize)> = roots.into_iter().rev().map(|i| (i, 0)).collect();
+    while let Some((i, level)) = stack.pop() {
+        result.push((i, level));
+        if let Some(children) = children.remove(&procs[i].id()) {
+            stack.extend(children.into_iter().rev().map(|c| (c, level + 1)));
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::myprocess::Metric;
+
+    #[test]
+    fn test_summarise_small() {
+        let row = |pid, parent, memory, prefix: &str| ProcRow {
+            proc: MyProcess {
+                pid,
+                parent,
+                memory,
+                depth: prefix.chars().count() + 1,
+                ..Default::default()
+            },
+            prefix: prefix.to_string(),
+            hidden: 0,
+        };
+        let rows = vec![
+            row(1, 0, 100, ""),
+            row(2, 1, 80, "├"),
+            row(3, 1, 5, "├"),
+            row(4, 3, 1, " └"),
+            row(5, 1, 2, "└"),
+        ];
+        let rows = summarise_small(rows, 10);
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[2].hidden, 3);
+        assert_eq!(rows[2].prefix, "└");
+        assert_eq!(rows[2].proc.total(), 7);
+
+        //the last real sibling and its descendants lead down to the summary
+        let rows = vec![
+            row(1, 0, 100, ""),
+            row(3, 1, 5, "├"),
+            row(2, 1, 80, "└"),
+            row(6, 2, 70, " └"),
+        ];
+        let rows = summarise_small(rows, 10);
+        let prefixes: Vec<&str> = rows.iter().map(|r| r.prefix.as_str()).collect();
+        assert_eq!(prefixes, vec!["", "├", "│└", "└"]);
+
+        //sorted by another column, the small siblings are not last
+        let rows = vec![
+            row(1, 0, 100, ""),
+            row(3, 1, 5, "├"),
+            row(2, 1, 80, "├"),
+            row(6, 2, 1, "│└"),
+            row(5, 1, 2, "└"),
+            row(7, 0, 50, ""),
+        ];
+        let rows = summarise_small(rows, 10);
+        let pids: Vec<usize> = rows.iter().map(|r| r.proc.pid).collect();
+        assert_eq!(pids, vec![1, 2, 0, 0, 7]);
+        assert_eq!((rows[2].hidden, rows[2].proc.parent), (1, 2));
+        assert_eq!((rows[3].hidden, rows[3].proc.parent), (2, 1));
+    }
+
+    #[test]
+    fn test_tree_mode() {
+        let proc = |pid, parent, cpu| MyProcess {
+            pid,
+            parent,
+            memory: 1,
+            cpu,
+            ..Default::default()
+        };
+        //1 has children 2 and 3, 2 has 4, 5 is another root
+        let procs = vec![
+            proc(1, 0, 0.1),
+            proc(2, 1, 1.),
+            proc(3, 1, 9.),
+            proc(4, 2, 5.),
+            proc(5, 0, 0.5),
+        ];
+        let mut sorted = SortedProcesses::new();
+        sorted.tree_mode = true;
+        sorted.sort_col = Column::Metric(Metric::Cpu);
+        let rows = |sorted: &SortedProcesses| -> Vec<(usize, String)> {
+            sorted
+                .rows()
+                .iter()
+                .map(|r| (r.proc.pid, r.prefix.clone()))
+                .collect()
+        };
+
+        sorted.sort_type = SortType::Descending;
+        sorted.update(&procs);
+        let expected = [(5, ""), (1, ""), (3, "├"), (2, "└"), (4, " └")];
+        let expected: Vec<(usize, String)> =
+            expected.iter().map(|(p, s)| (*p, s.to_string())).collect();
+        assert_eq!(rows(&sorted), expected);
+
+        sorted.sort_type = SortType::Ascending;
+        sorted.update(&procs);
+        let expected = [(1, ""), (2, "├"), (4, "│└"), (3, "└"), (5, "")];
+        let expected: Vec<(usize, String)> =
+            expected.iter().map(|(p, s)| (*p, s.to_string())).collect();
+        assert_eq!(rows(&sorted), expected);
+
+        //without tree_mode the list is flat
+        sorted.tree_mode = false;
+        sorted.update(&procs);
+        let pids: Vec<usize> = sorted.rows().iter().map(|r| r.proc.pid).collect();
+        assert_eq!(pids, vec![1, 5, 2, 4, 3]);
+        assert!(sorted.rows().iter().all(|r| r.prefix.is_empty()));
+
+        //6 has the pid of 1 as parent, but its parent started at another time
+        let mut procs = procs;
+        procs.push(MyProcess {
+            parent_start_time: 99,
+            ..proc(6, 1, 0.2)
+        });
+        sorted.tree_mode = true;
+        sorted.update(&procs);
+        let expected = [(1, ""), (2, "├"), (4, "│└"), (3, "└"), (6, ""), (5, "")];
+        let expected: Vec<(usize, String)> =
+            expected.iter().map(|(p, s)| (*p, s.to_string())).collect();
+        assert_eq!(rows(&sorted), expected);
+    }
+
+    #[test]
+    fn test_hidezeros_keeps_zombies() {
+        let procs = vec![
+            MyProcess {
+                pid: 1,
+                memory: 1,
+                ..Default::default()
+            },
+            MyProcess {
+                pid: 2,
+                parent: 1,
+                ..Default::default()
+            },
+            MyProcess {
+                pid: 3,
+                parent: 1,
+                state: ProcState::Zombie,
+                ..Default::default()
+            },
+        ];
+        let mut sorted = SortedProcesses::new();
+        sorted.update(&procs);
+        let pids: Vec<usize> = sorted.rows().iter().map(|r| r.proc.pid).collect();
+        assert_eq!(pids, vec![1, 3]);
+
+        sorted.state_filter = Some(ProcState::Zombie);
+        sorted.calc();
+        assert_eq!(sorted.rows().len(), 1);
+    }
+
+    #[test]
+    fn test_family() {
+        let proc = |pid, parent| MyProcess {
+            pid,
+            parent,
+            memory: 1,
+            ..Default::default()
+        };
+        //1 has children 2 and 5, 2 has 3 which has 4
+        let procs = vec![proc(1, 0), proc(2, 1), proc(3, 2), proc(4, 3), proc(5, 1)];
+        let mut sorted = SortedProcesses::new();
+        sorted.update(&procs);
+        let id = procs[2].id();
+        let ancestors: Vec<usize> = sorted.ancestors(&id).iter().map(|p| p.pid).collect();
+        assert_eq!(ancestors, vec![2, 1]);
+
+        sorted.family = Some(id);
+        sorted.calc();
+        let mut pids: Vec<usize> = sorted.rows().iter().map(|r| r.proc.pid).collect();
+        pids.sort();
+        assert_eq!(pids, vec![1, 2, 3, 4]);
+
+        //the last process in tree order is shown too
+        sorted.family = None;
+        sorted.calc();
+        assert_eq!(sorted.rows().len(), 5);
+    }
+
+    #[test]
+    fn test_collapse_levels() {
+        let proc = |pid, parent, depth| MyProcess {
+            pid,
+            parent,
+            depth,
+            memory: 1,
+            ..Default::default()
+        };
+        //1 has 2 and 5, 2 has 3 which has 4, 6 is another root with 7
+        let procs = vec![
+            proc(1, 0, 1),
+            proc(2, 1, 2),
+            proc(3, 2, 3),
+            proc(4, 3, 4),
+            proc(5, 1, 2),
+            proc(6, 0, 1),
+            proc(7, 6, 2),
+        ];
+        let mut sorted = SortedProcesses::new();
+        sorted.update(&procs);
+        let pids = |sorted: &SortedProcesses| -> Vec<usize> {
+            sorted.rows().iter().map(|r| r.proc.pid).collect()
+        };
+
+        sorted.collapse_all();
+        assert_eq!(pids(&sorted), vec![1, 6]);
+        assert_eq!(sorted.visible_depth(), 1);
+
+        sorted.collapse_to_depth(2);
+        assert_eq!(pids(&sorted), vec![1, 2, 5, 6, 7]);
+        assert_eq!(sorted.visible_depth(), 2);
+
+        //only the path to 4 is expanded
+        sorted.expand_to(procs[3].id());
+        assert_eq!(pids(&sorted), vec![1, 2, 3, 4, 5, 6]);
+
+        sorted.expand_all();
+        assert_eq!(sorted.rows().len(), 7);
     }
-    vec
 }
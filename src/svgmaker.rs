@@ -1,6 +1,41 @@
 use std::cmp::Reverse;
 
-use crate::{helpers::nice_size, myprocess::MyProcess};
+use crate::{
+    helpers::{mix_rgb, nice_size},
+    myprocess::MyProcess,
+};
+
+///Colors of the svg as red, green and blue
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SvgColors {
+    ///own memory of small processes
+    pub memory_low: (u8, u8, u8),
+    ///own memory of the largest process
+    pub memory_high: (u8, u8, u8),
+    ///background of children memory
+    pub children: (u8, u8, u8),
+}
+
+impl Default for SvgColors {
+    fn default() -> Self {
+        SvgColors {
+            memory_low: (255, 255, 150),
+            memory_high: (255, 0, 0),
+            children: (190, 196, 255),
+        }
+    }
+}
+
+impl SvgColors {
+    ///css color of own memory, ratio from 0 to 1 of the largest process
+    pub fn memory(&self, ratio: f32) -> String {
+        css(mix_rgb(self.memory_low, self.memory_high, ratio))
+    }
+}
+
+fn css((r, g, b): (u8, u8, u8)) -> String {
+    format!("rgb({r}, {g}, {b})")
+}
 
 static ROOT: &str = r##"<?xml version="1.0" standalone="no"?><!DOCTYPE svg PUBLIC "-//W3C//DTD SVG 1.1//EN" "http://www.w3.org/Graphics/SVG/1.1/DTD/svg11.dtd">
 <svg version="1.1" width="99%" height="100%" viewBox="0 0 1000 300" xmlns="http://www.w3.org/2000/svg"  >
@@ -8,19 +43,20 @@ static ROOT: &str = r##"<?xml version="1.0" standalone="no"?><!DOCTYPE svg PUBLI
 text { font-family:monospace; font-size:14px }
 g.hoverable:hover rect { stroke:#ff2288; stroke-width:2; cursor:pointer;  }
 </style>
-<text x="0" y="20" fill="white">Blue is total children memory, whereas own memory is gradient from red (highest) to yellow (lowest)</text>
+<text x="0" y="20" fill="white">Background is total children memory, whereas own memory is a gradient from highest to lowest</text>
   [SVG]
 </svg>
 "##;
 
-pub fn generate_svg(procs: &[MyProcess]) -> String {
+///flamegraph of memory, each process is drawn above its parent
+pub fn generate_svg(procs: &[MyProcess], colors: &SvgColors) -> String {
     let mut svg = r##"<svg id="data" x="0" y="30" width="1000">"##.to_string();
 
     let height = 20.;
     let width = 1000.; //width as f32;
 
     let mut layers = vec![vec![]];
-    draw_pid(0, procs, 0., width, height, 0, 0, &mut layers);
+    draw_pid(0, procs, 0., width, height, 0, 0, colors, &mut layers);
     let total_stat: u64 = procs.iter().map(|s| s.memory).sum();
     for layer in layers {
         for item in layer {
@@ -59,7 +95,7 @@ pub fn generate_svg(procs: &[MyProcess]) -> String {
                 item.y,
                 item.total_width,
                 height,
-                "rgb(190, 196, 255)",
+                css(colors.children),
                 //own rect
                 item.x + item.children_width,
                 item.y + 0.25,
@@ -101,6 +137,7 @@ fn draw_pid(
     height: f32,
     mut current_depth_total: u64,
     depth: usize,
+    colors: &SvgColors,
     layers: &mut Vec<Vec<LayerProc>>,
 ) {
     let mut vals: Vec<u64> = procs.iter().map(|s| s.memory).collect();
@@ -138,9 +175,7 @@ fn draw_pid(
     }
 
     let ratio = (t.memory as f32 / max_mem as f32).powf(0.3);
-    let g = ((1.0 - ratio) * 255.0) as u32;
-    let b = ((1.0 - ratio) * 150.0) as u32;
-    let col = format!("rgb(255, {g}, {b})");
+    let col = colors.memory(ratio);
 
     let y = depth as f32 * (height + 1.);
 
@@ -173,6 +208,7 @@ fn draw_pid(
             height,
             total,
             depth + 1,
+            colors,
             layers,
         );
         let mut child_width = child.total() as f32 / total as f32 * (total_width - own_width);
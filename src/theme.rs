@@ -0,0 +1,254 @@
+use ratatui::style::Color;
+use serde::{Deserialize, Serialize};
+use toprs::{SvgColors, helpers::mix_rgb};
+
+///Colors used by the tui, svg and gui
+///colors are names like "lightblue", hex like "#ff8800" or terminal indexes like "208"
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct Theme {
+    #[serde(with = "color")]
+    pub gradient_low: Color, //usage gradient of the gauges
+    #[serde(with = "color")]
+    pub gradient_high: Color,
+    #[serde(with = "color")]
+    pub gauge_empty: Color,
+    #[serde(with = "color")]
+    pub memory_low: Color, //own memory gradient of the svg and sunburst
+    #[serde(with = "color")]
+    pub memory_high: Color,
+    #[serde(with = "color")]
+    pub children: Color, //children memory in the svg
+    #[serde(with = "color")]
+    pub chart_text: Color, //labels outside the sunburst
+    #[serde(with = "color")]
+    pub header_fg: Color,
+    #[serde(with = "color")]
+    pub header_bg: Color,
+    #[serde(with = "color")]
+    pub sort_fg: Color,
+    #[serde(with = "color")]
+    pub sort_bg: Color,
+    #[serde(with = "color")]
+    pub selected_fg: Color,
+    #[serde(with = "color")]
+    pub selected_bg: Color,
+    #[serde(with = "color")]
+    pub followed_bg: Color, //selection which follows a process
+    #[serde(with = "color")]
+    pub popup_fg: Color,
+    #[serde(with = "color")]
+    pub popup_bg: Color,
+    #[serde(with = "color")]
+    pub popup_border: Color,
+    #[serde(with = "color")]
+    pub input_fg: Color,
+    #[serde(with = "color")]
+    pub input_bg: Color,
+    #[serde(with = "color")]
+    pub top_memory: Color,
+    #[serde(with = "color")]
+    pub top_cpu: Color,
+    #[serde(with = "color")]
+    pub collapsed: Color,
+    #[serde(with = "color")]
+    pub zombie: Color,
+    #[serde(with = "color")]
+    pub disk_sleep: Color,
+    #[serde(with = "color")]
+    pub exited: Color,
+    #[serde(with = "color")]
+    pub error: Color,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme {
+            gradient_low: Color::Rgb(255, 255, 0),
+            gradient_high: Color::Rgb(255, 0, 0),
+            gauge_empty: Color::DarkGray,
+            memory_low: Color::Rgb(255, 255, 150),
+            memory_high: Color::Rgb(255, 0, 0),
+            children: Color::Rgb(190, 196, 255),
+            chart_text: Color::Rgb(55, 126, 184),
+            header_fg: Color::Black,
+            header_bg: Color::LightBlue,
+            sort_fg: Color::White,
+            sort_bg: Color::LightRed,
+            selected_fg: Color::Black,
+            selected_bg: Color::LightYellow,
+            followed_bg: Color::LightRed,
+            popup_fg: Color::Black,
+            popup_bg: Color::Yellow,
+            popup_border: Color::Red,
+            input_fg: Color::Black,
+            input_bg: Color::Green,
+            top_memory: Color::LightRed,
+            top_cpu: Color::Magenta,
+            collapsed: Color::LightGreen,
+            zombie: Color::Red,
+            disk_sleep: Color::Yellow,
+            exited: Color::DarkGray,
+            error: Color::Red,
+        }
+    }
+}
+
+impl Theme {
+    pub const NAMES: [&str; 3] = ["default", "light", "colorblind"];
+
+    ///built in theme by name
+    pub fn named(name: &str) -> Option<Theme> {
+        let theme = match name {
+            "default" => Theme::default(),
+            //for terminals with a light background
+            "light" => Theme {
+                gradient_low: Color::Rgb(200, 140, 0),
+                gradient_high: Color::Rgb(200, 0, 0),
+                gauge_empty: Color::Gray,
+                memory_low: Color::Rgb(200, 140, 0),
+                memory_high: Color::Rgb(200, 0, 0),
+                children: Color::Rgb(120, 130, 230),
+                header_fg: Color::White,
+                header_bg: Color::Blue,
+                sort_fg: Color::White,
+                sort_bg: Color::Red,
+                selected_bg: Color::LightCyan,
+                followed_bg: Color::LightMagenta,
+                popup_bg: Color::LightYellow,
+                input_fg: Color::White,
+                top_memory: Color::Red,
+                collapsed: Color::Green,
+                disk_sleep: Color::Rgb(160, 100, 0),
+                exited: Color::Gray,
+                ..Theme::default()
+            },
+            //avoids red against green, based on the Okabe-Ito palette
+            "colorblind" => Theme {
+                gradient_low: Color::Rgb(86, 180, 233),
+                gradient_high: Color::Rgb(230, 159, 0),
+                memory_low: Color::Rgb(86, 180, 233),
+                memory_high: Color::Rgb(230, 159, 0),
+                children: Color::Rgb(166, 206, 227),
+                chart_text: Color::Rgb(0, 114, 178),
+                header_fg: Color::White,
+                header_bg: Color::Rgb(0, 114, 178),
+                sort_fg: Color::Black,
+                sort_bg: Color::Rgb(230, 159, 0),
+                followed_bg: Color::Rgb(204, 121, 167),
+                popup_bg: Color::Rgb(240, 228, 66),
+                popup_border: Color::Rgb(0, 114, 178),
+                input_bg: Color::Rgb(86, 180, 233),
+                top_memory: Color::Rgb(230, 159, 0),
+                top_cpu: Color::Rgb(204, 121, 167),
+                collapsed: Color::Rgb(86, 180, 233),
+                zombie: Color::Rgb(213, 94, 0),
+                disk_sleep: Color::Rgb(240, 228, 66),
+                error: Color::Rgb(213, 94, 0),
+                ..Theme::default()
+            },
+            _ => return None,
+        };
+        Some(theme)
+    }
+
+    ///usage color between low and high, val from 0 to 1
+    pub fn gradient(&self, val: f32) -> Color {
+        let (r, g, b) = mix_rgb(rgb(self.gradient_low), rgb(self.gradient_high), val);
+        Color::Rgb(r, g, b)
+    }
+
+    ///colors of the svg and sunburst
+    pub fn svg_colors(&self) -> SvgColors {
+        SvgColors {
+            memory_low: rgb(self.memory_low),
+            memory_high: rgb(self.memory_high),
+            children: rgb(self.children),
+        }
+    }
+}
+
+///css color for the gui
+#[cfg(any(feature = "gui", test))]
+pub fn css(color: Color) -> String {
+    let (r, g, b) = rgb(color);
+    format!("rgb({r}, {g}, {b})")
+}
+
+///approximate rgb of terminal colors, using the xterm defaults
+fn rgb(color: Color) -> (u8, u8, u8) {
+    const LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+    match color {
+        Color::Rgb(r, g, b) => (r, g, b),
+        Color::Black | Color::Reset | Color::Indexed(0) => (0, 0, 0),
+        Color::Red | Color::Indexed(1) => (205, 0, 0),
+        Color::Green | Color::Indexed(2) => (0, 205, 0),
+        Color::Yellow | Color::Indexed(3) => (205, 205, 0),
+        Color::Blue | Color::Indexed(4) => (0, 0, 238),
+        Color::Magenta | Color::Indexed(5) => (205, 0, 205),
+        Color::Cyan | Color::Indexed(6) => (0, 205, 205),
+        Color::Gray | Color::Indexed(7) => (229, 229, 229),
+        Color::DarkGray | Color::Indexed(8) => (127, 127, 127),
+        Color::LightRed | Color::Indexed(9) => (255, 0, 0),
+        Color::LightGreen | Color::Indexed(10) => (0, 255, 0),
+        Color::LightYellow | Color::Indexed(11) => (255, 255, 0),
+        Color::LightBlue | Color::Indexed(12) => (92, 92, 255),
+        Color::LightMagenta | Color::Indexed(13) => (255, 0, 255),
+        Color::LightCyan | Color::Indexed(14) => (0, 255, 255),
+        Color::White | Color::Indexed(15) => (255, 255, 255),
+        //6x6x6 color cube then grayscale
+        Color::Indexed(i @ 16..=231) => {
+            let i = (i - 16) as usize;
+            (LEVELS[i / 36], LEVELS[i / 6 % 6], LEVELS[i % 6])
+        }
+        Color::Indexed(i) => {
+            let level = 8 + (i - 232) * 10;
+            (level, level, level)
+        }
+    }
+}
+
+///stores colors as strings, which is what ratatui parses
+mod color {
+    use ratatui::style::Color;
+    use serde::{Deserialize, Deserializer, Serializer, de::Error};
+
+    pub fn serialize<S: Serializer>(color: &Color, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&color.to_string())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Color, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        name.parse()
+            .map_err(|_| D::Error::custom(format!("unknown color {name}")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_theme() {
+        let theme: Theme =
+            toml::from_str("header_bg = \"#102030\"\ntop_cpu = \"light-cyan\"").unwrap();
+        assert_eq!(theme.header_bg, Color::Rgb(16, 32, 48));
+        assert_eq!(theme.top_cpu, Color::LightCyan);
+        assert_eq!(theme.zombie, Theme::default().zombie);
+        assert!(toml::from_str::<Theme>("zombie = \"nope\"").is_err());
+
+        assert_eq!(theme.gradient(0.5), Color::Rgb(255, 127, 0));
+        //the default matches the original svg colors
+        assert_eq!(theme.svg_colors(), SvgColors::default());
+        let ratio = 0.3f32;
+        let (g, b) = (((1. - ratio) * 255.) as u8, ((1. - ratio) * 150.) as u8);
+        assert_eq!(
+            theme.svg_colors().memory(ratio),
+            format!("rgb(255, {g}, {b})")
+        );
+        assert_eq!(css(Color::Indexed(196)), "rgb(255, 0, 0)");
+        for name in Theme::NAMES {
+            assert!(Theme::named(name).is_some());
+        }
+    }
+}
@@ -1,60 +1,138 @@
 use ratatui::{style::Color, widgets::Widget};
 
+//more mounts, like snap loop devices, would push the table off screen
+const MAX_DISK_ROWS: usize = 5;
+
+//by name, as the numbers differ between linux and macos
+const SIGCONT: &str = "CONT";
+const SIGSTOP: &str = "STOP";
+
 struct State {
     //data
-    procs: HashMap<usize, MyProcess>, //pid->proc
+    procs: HashMap<ProcId, MyProcess>,
+    exited: HashMap<ProcId, MyProcess>, //recently exited, shown greyed out but not actionable
+    churn: Churn,
     visible: SortedProcesses,
     totals: Totals,
-    top5memory: Vec<usize>,
-    top5cpu: Vec<usize>,
+    top5memory: Vec<ProcId>,
+    top5cpu: Vec<ProcId>,
     networks: Vec<MyNetwork>,
+    disks: Vec<MyDisk>,
 
     //view state
-    show_info: Option<usize>,
+    show_info: Option<ProcId>,
+    info: Option<ProcessDetails>,
+    info_scroll: u16,
+    info_searching: bool, //searching environment variables in info
+    info_search: String,
+    info_tab: InfoTab,
+    open_files: Option<Result<Vec<OpenFd>, String>>,
     show_kill: bool,
     show_help: bool,
     filter: String,
     filtering: bool,
+    view: Option<String>,        //name of last applied view
+    naming_view: Option<String>, //entering name to save current view
     selected: Selected,
     start_gui: bool,
     kill_signal: usize,
     kill_process: Option<MyProcess>,
-    searching: bool, //change selection to match
-    search: String,  //for changing selection search
+    signal_tree: bool, //send signal to process and all its descendants
+    affinity: Option<AffinityEdit>,
+    smaps: Option<SmapsView>,
+    threads: Option<ThreadsView>,
+    column_picker: Option<usize>, //selected row in the column picker
+    searching: bool,              //change selection to match
+    search: String,               //for changing selection search
     config: Config,
+    theme: Theme,
+    keymap: Keymap,
+}
+struct AffinityEdit {
+    proc: MyProcess,
+    cores: Vec<bool>,
+    core: usize, //selected core
+    tree: bool,  //apply to process and all its descendants
+    error: Option<String>,
+}
+#[derive(Clone, Copy, PartialEq)]
+enum InfoTab {
+    Details,
+    Files,
+    Ancestry,
+}
+struct SmapsView {
+    proc: MyProcess,
+    groups: Result<Vec<MappingGroup>, String>,
+    scroll: usize,
+}
+struct ThreadsView {
+    proc: MyProcess,
+    threads: Option<Vec<MyThread>>, //loaded on open and every update
+    scroll: usize,
 }
 enum Selected {
     Index(usize),
-    Proc(usize),
+    Proc(ProcId),
 }
 
 pub fn run(config: Config) -> Result<bool, std::io::Error> {
     enable_raw_mode()?;
     let mut stdout = std::io::stdout();
-    let mut man = manager::ProcManager::new();
+    let mut man = ProcManager::new();
     let mut state = State {
-        procs: man.procs().iter().map(|a| (a.pid, a.clone())).collect(),
+        procs: man.procs().iter().map(|a| (a.id(), a.clone())).collect(),
+        exited: HashMap::new(),
+        churn: man.churn().clone(),
         visible: SortedProcesses::new(),
         selected: Selected::Index(0),
         kill_signal: 9,
         totals: man.get_totals(),
+        theme: config.theme(),
+        keymap: config.keymap(),
         config,
 
         kill_process: None,
+        signal_tree: false,
+        affinity: None,
+        smaps: None,
+        threads: None,
+        column_picker: None,
         top5memory: vec![],
         top5cpu: vec![],
         start_gui: false,
         filter: String::new(),
         filtering: false,
+        view: None,
+        naming_view: None,
         show_info: None,
+        info: None,
+        info_scroll: 0,
+        info_searching: false,
+        info_search: String::new(),
+        info_tab: InfoTab::Details,
+        open_files: None,
         show_kill: false,
         show_help: false,
         searching: false,
         search: String::new(),
         networks: vec![],
+        disks: man.get_disks(),
     };
-    state.visible.sort_col = state.config.tui.sort_column;
+    state.visible.sort_col =
+        Column::from_id(&state.config.tui.sort_column).unwrap_or(Column::Command);
+    let columns = Column::from_ids(&state.config.tui.columns);
+    if !columns.is_empty() {
+        state.visible.columns = columns;
+    }
     state.visible.sort_type = state.config.tui.sort_type;
+    state.visible.tree_mode = state.config.tui.tree_mode;
+    state.visible.tree_totals = state.config.tui.show_tree_totals;
+    state.visible.hide_kernel_threads = !state.config.tui.show_kernel_threads;
+    state.visible.keep_ancestors = state.config.tui.filter_ancestors;
+    if state.config.tui.auto_collapse {
+        state.visible.auto_collapse = Some(state.auto_collapse_below());
+    }
     state.sort();
     execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
 
@@ -69,11 +147,27 @@ pub fn run(config: Config) -> Result<bool, std::io::Error> {
         terminal.draw(|f| {
             //get update if necessary
             if last.elapsed().as_secs_f32() > state.config.tui.update_interval {
+                man.set_tracked(&state.visible.metrics());
                 man.update();
-                state.procs = man.procs().iter().map(|a| (a.pid, a.clone())).collect();
+                state.procs = man.procs().iter().map(|a| (a.id(), a.clone())).collect();
+                state.exited = man
+                    .exited()
+                    .iter()
+                    .map(|e| (e.proc.id(), e.proc.clone()))
+                    .collect();
+                state.churn = man.churn().clone();
                 state.sort();
                 state.totals = man.get_totals();
                 state.networks = man.get_networks();
+                state.disks = man.get_disks();
+                state.info = state.show_info.and_then(|id| man.details(id.pid));
+                state.load_open_files();
+                if let Some(view) = &mut state.smaps {
+                    view.groups = smaps::load(view.proc.pid);
+                }
+                if let Some(view) = &mut state.threads {
+                    view.threads = Some(man.threads(view.proc.pid));
+                }
                 last = Instant::now();
                 sync_selection(&mut state, &mut tablestate);
             }
@@ -82,29 +176,53 @@ pub fn run(config: Config) -> Result<bool, std::io::Error> {
             draw_table(f, &state, &mut tablestate);
 
             if state.show_help {
-                draw_help(f);
+                draw_help(f, &state);
             }
-            if let Some(pid) = state.show_info
-                && let Some(proc) = state.procs.get(&pid)
+            //details are loaded when opened, and then on every update
+            if state.show_info.is_some()
+                && state.info.as_ref().map(|d| d.pid) != state.show_info.map(|id| id.pid)
             {
-                let parent = if let Some(pproc) = state.procs.get(&proc.parent) {
-                    &pproc.name
-                } else {
-                    ""
-                };
-                draw_process_info(f, proc, parent);
+                state.info = state.show_info.and_then(|id| man.details(id.pid));
+                state.load_open_files();
+            }
+            if let Some(id) = state.show_info
+                && let Some(proc) = state.procs.get(&id)
+            {
+                draw_process_info(f, proc, &state);
             }
             if state.show_kill {
                 draw_kill(f, &mut tablestate_kill, &state);
             }
+            if let Some(edit) = &state.affinity {
+                draw_affinity(f, edit, &state);
+            }
+            if let Some(selected) = state.column_picker {
+                draw_column_picker(f, selected, &state);
+            }
+            if let Some(view) = &mut state.threads
+                && view.threads.is_none()
+            {
+                view.threads = Some(man.threads(view.proc.pid));
+            }
+            if let Some(view) = &state.threads {
+                draw_threads(f, view, &state.theme);
+            }
+            if let Some(view) = &state.smaps {
+                draw_smaps(f, view, &state.theme);
+            }
 
-            if state.searching {
+            if let Some(name) = &state.naming_view {
+                draw_view_name(f, &state, name);
+            } else if state.searching {
                 draw_search(f, &state);
-            } else if state.filtering || !state.filter.is_empty() {
+            } else if state.filtering
+                || !state.filter.is_empty()
+                || state.visible.state_filter.is_some()
+            {
                 draw_filter(f, &state);
             }
 
-            handle_input(&mut done, &mut state);
+            handle_input(&mut done, &mut state, tablestate.offset());
 
             sync_selection(&mut state, &mut tablestate);
 
@@ -114,8 +232,14 @@ pub fn run(config: Config) -> Result<bool, std::io::Error> {
     }
 
     //save config
-    state.config.tui.sort_column = state.visible.sort_col;
+    state.config.tui.sort_column = state.visible.sort_col.id();
+    state.config.tui.columns = state.visible.columns.iter().map(|c| c.id()).collect();
     state.config.tui.sort_type = state.visible.sort_type;
+    state.config.tui.tree_mode = state.visible.tree_mode;
+    state.config.tui.show_tree_totals = state.visible.tree_totals;
+    state.config.tui.show_kernel_threads = !state.visible.hide_kernel_threads;
+    state.config.tui.filter_ancestors = state.visible.keep_ancestors;
+    state.config.tui.auto_collapse = state.visible.auto_collapse.is_some();
     state.config.save();
 
     // restore terminal
@@ -130,7 +254,7 @@ pub fn run(config: Config) -> Result<bool, std::io::Error> {
 }
 
 fn sync_selection(state: &mut State, tablestate: &mut TableState) {
-    if state.visible.procs().is_empty() {
+    if state.visible.rows().is_empty() {
         tablestate.select(None);
         return;
     }
@@ -138,11 +262,10 @@ fn sync_selection(state: &mut State, tablestate: &mut TableState) {
     //ensure selected is in limits
     match state.selected {
         Selected::Index(ind) => {
-            state.selected =
-                Selected::Index(ind.min(state.visible.procs().len().saturating_sub(1)));
+            state.selected = Selected::Index(ind.min(state.visible.rows().len().saturating_sub(1)));
         }
-        Selected::Proc(pid) => {
-            if !state.procs.contains_key(&pid) {
+        Selected::Proc(id) => {
+            if !state.procs.contains_key(&id) {
                 state.selected = Selected::Index(0);
             }
         }
@@ -185,13 +308,21 @@ fn draw_kill(f: &mut Frame<'_>, tablestate: &mut TableState, state: &State) {
         ROWS.iter().map(|a| Row::new([*a])).collect::<Vec<_>>(),
         widths,
     )
-    .row_highlight_style(Style::default().bg(Color::LightYellow).fg(Color::Black))
+    .row_highlight_style(
+        Style::default()
+            .bg(state.theme.selected_bg)
+            .fg(state.theme.selected_fg),
+    )
     .block(
         Block::default()
             .borders(Borders::ALL)
             .padding(Padding::horizontal(2))
-            .border_style(Style::default().fg(Color::Red))
+            .border_style(Style::default().fg(state.theme.popup_border))
             .title(format!("Send signal to {} ({})", proc.name, proc.pid))
+            .title_bottom(format!(
+                "t: whole tree [{}]",
+                if state.signal_tree { "on" } else { "off" }
+            ))
             .border_type(BorderType::Rounded),
     );
 
@@ -203,38 +334,259 @@ fn draw_kill(f: &mut Frame<'_>, tablestate: &mut TableState, state: &State) {
     f.render_stateful_widget(t, rect, tablestate);
 }
 
-fn draw_process_info(f: &mut Frame<'_>, proc: &MyProcess, parent: &str) {
-    let mut lines = vec![
-        format!("PID: {}", proc.pid),
-        format!("Name: {}", proc.name),
-        format!("Command Line: {}", proc.command),
-        format!("Memory (self):     {:>10}", nice_size(proc.memory)),
-        format!("Memory (children): {:>10}", nice_size(proc.children_memory)),
-        format!("Memory (total):    {:>10}", nice_size(proc.total())),
-        format!("CPU: {:>5.1}%", proc.cpu),
-        format!("Run Time: {}", nice_time(proc.run_time)),
+///visible columns in order, followed by the hidden ones
+fn picker_columns(visible: &[Column]) -> Vec<(Column, bool)> {
+    let mut columns: Vec<(Column, bool)> = visible.iter().map(|c| (*c, true)).collect();
+    columns.extend(
+        Column::ALL
+            .iter()
+            .filter(|c| !visible.contains(c))
+            .map(|c| (*c, false)),
+    );
+    columns
+}
+
+fn draw_column_picker(f: &mut Frame<'_>, selected: usize, state: &State) {
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title("Columns")
+        .title_bottom("space: show/hide  </>: move  esc: close")
+        .border_type(BorderType::Rounded);
+    let columns = picker_columns(&state.visible.columns);
+    let rect = f.area().centered(
+        Constraint::Length(44),
+        Constraint::Length(columns.len() as u16 + 2),
+    );
+    f.render_widget(Clear, rect);
+
+    let rows = columns.iter().map(|(col, shown)| {
+        let mark = if *shown { "✓" } else { " " };
+        Row::new([format!("{mark} {}", col.header()), col.id()])
+    });
+    let t = Table::new(rows, [Constraint::Length(20), Constraint::Min(10)])
+        .row_highlight_style(
+            Style::default()
+                .bg(state.theme.selected_bg)
+                .fg(state.theme.selected_fg),
+        )
+        .block(block);
+    //scrolls to the selection when the terminal is lower than the list
+    let mut tablestate = TableState::default();
+    tablestate.select(Some(selected));
+    f.render_stateful_widget(t, rect, &mut tablestate);
+}
+
+fn draw_affinity(f: &mut Frame<'_>, edit: &AffinityEdit, state: &State) {
+    let lines = vec![
+        format!(
+            "Allowed cores: {}/{}",
+            edit.cores.iter().filter(|c| **c).count(),
+            edit.cores.len()
+        ),
+        format!(
+            "Selected core: {}  usage: {:.1}%",
+            edit.core + 1,
+            state
+                .totals
+                .cpus
+                .get(edit.core)
+                .copied()
+                .unwrap_or_default()
+        ),
+        format!("Whole tree: {}", if edit.tree { "on" } else { "off" }),
+        edit.error.clone().unwrap_or_default(),
+        "←/→/↑/↓ core  space: toggle  a: all".to_string(),
+        "t: tree  enter: apply  esc: cancel".to_string(),
     ];
-    if !parent.is_empty() {
-        lines.insert(3, format!("Parent PID: {:?}", proc.parent));
-        lines.insert(4, format!("Parent Name: {}", parent));
-    }
+    let p = Paragraph::new(lines.into_iter().map(Line::from).collect::<Vec<Line>>())
+        .style(
+            Style::default()
+                .bg(state.theme.popup_bg)
+                .fg(state.theme.popup_fg),
+        )
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(format!(
+                    "CPU affinity of {} ({})",
+                    edit.proc.name, edit.proc.pid
+                ))
+                .border_type(BorderType::Rounded),
+        );
+    let rect = f
+        .area()
+        .centered(Constraint::Length(50), Constraint::Length(8));
+    f.render_widget(Clear, rect);
+    f.render_widget(p, rect);
+}
+
+fn draw_smaps(f: &mut Frame<'_>, view: &SmapsView, theme: &Theme) {
+    let title = format!("Memory map of {} ({})", view.proc.name, view.proc.pid);
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(title)
+        .title_bottom("↑/↓ scroll  esc: close")
+        .border_type(BorderType::Rounded);
+    let rect = f.area().centered(
+        Constraint::Length(f.area().width.saturating_sub(8)),
+        Constraint::Length(f.area().height.saturating_sub(4)),
+    );
+    f.render_widget(Clear, rect);
 
-    let cmd_width = lines[2].len() as u16 + 2;
-    let p = Paragraph::new(
-        lines
+    let groups = match &view.groups {
+        Ok(groups) => groups,
+        Err(err) => {
+            let p = Paragraph::new(err.as_str())
+                .style(Style::default().bg(theme.popup_bg).fg(theme.popup_fg))
+                .block(block);
+            f.render_widget(p, rect);
+            return;
+        }
+    };
+
+    let total = |val: fn(&MappingGroup) -> u64| nice_size(groups.iter().map(val).sum());
+    let header = Row::new(
+        ["Kind", "Count", "RSS", "PSS", "Swap", "Mapping"]
             .iter()
-            .map(|a| Line::from(a.clone()))
-            .collect::<Vec<Line>>(),
+            .enumerate()
+            .map(|(i, h)| match i {
+                1..=4 => format!("{h:>10}"),
+                _ => h.to_string(),
+            }),
     )
-    .style(Style::default().bg(Color::Yellow).fg(Color::Black))
-    .block(
-        Block::default()
-            .borders(Borders::ALL)
-            .title(proc.name.clone())
-            .border_type(BorderType::Rounded),
+    .style(Style::default().fg(theme.header_fg).bg(theme.header_bg));
+    let mut rows = vec![
+        Row::new([
+            "total".to_string(),
+            format!("{:>10}", groups.iter().map(|g| g.count).sum::<usize>()),
+            format!("{:>10}", total(|g| g.rss)),
+            format!("{:>10}", total(|g| g.pss)),
+            format!("{:>10}", total(|g| g.swap)),
+            String::new(),
+        ])
+        .style(Style::default().add_modifier(Modifier::BOLD)),
+    ];
+    rows.extend(groups.iter().skip(view.scroll).map(|g| {
+        Row::new([
+            g.kind.to_string(),
+            format!("{:>10}", g.count),
+            format!("{:>10}", nice_size(g.rss)),
+            format!("{:>10}", nice_size(g.pss)),
+            format!("{:>10}", nice_size(g.swap)),
+            g.name.clone(),
+        ])
+    }));
+    let widths = [
+        Constraint::Length(8),
+        Constraint::Length(10),
+        Constraint::Length(10),
+        Constraint::Length(10),
+        Constraint::Length(10),
+        Constraint::Min(20),
+    ];
+    let t = Table::new(rows, widths).header(header).block(block);
+    f.render_widget(t, rect);
+}
+
+fn draw_threads(f: &mut Frame<'_>, view: &ThreadsView, theme: &Theme) {
+    let title = format!("Threads of {} ({})", view.proc.name, view.proc.pid);
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(title)
+        .title_bottom("↑/↓ scroll  esc: close")
+        .border_type(BorderType::Rounded);
+    let rect = f.area().centered(
+        Constraint::Length(f.area().width.saturating_sub(8).min(80)),
+        Constraint::Length(f.area().height.saturating_sub(4)),
     );
-    let w = 40.max(cmd_width).min(f.area().width - 4);
-    let h = lines.len() as u16 + 2;
+    f.render_widget(Clear, rect);
+
+    let threads = view.threads.as_deref().unwrap_or_default();
+    if threads.is_empty() {
+        let p = Paragraph::new("no threads found")
+            .style(Style::default().bg(theme.popup_bg).fg(theme.popup_fg))
+            .block(block);
+        f.render_widget(p, rect);
+        return;
+    }
+
+    let header = Row::new(
+        ["TID", "Name", "State", "CPU", "CPU Time"]
+            .iter()
+            .enumerate()
+            .map(|(i, h)| match i {
+                1 | 2 => h.to_string(),
+                _ => format!("{h:>10}"),
+            }),
+    )
+    .style(Style::default().fg(theme.header_fg).bg(theme.header_bg));
+    let rows = threads.iter().skip(view.scroll).map(|t| {
+        Row::new([
+            format!("{:>10}", t.tid),
+            t.name.clone(),
+            t.state.clone(),
+            format!("{:>10}", Metric::Cpu.format(t.cpu as f64)),
+            format!("{:>10}", Metric::CpuTime.format(t.cpu_time)),
+        ])
+    });
+    let widths = [
+        Constraint::Length(10),
+        Constraint::Min(16),
+        Constraint::Length(12),
+        Constraint::Length(10),
+        Constraint::Length(10),
+    ];
+    let t = Table::new(rows, widths).header(header).block(block);
+    f.render_widget(t, rect);
+}
+
+fn draw_process_info(f: &mut Frame<'_>, proc: &MyProcess, state: &State) {
+    let lines = state.info_lines();
+
+    let tab_style = |tab: InfoTab| {
+        if tab == state.info_tab {
+            Style::default().add_modifier(Modifier::REVERSED)
+        } else {
+            Style::default()
+        }
+    };
+    let title = Line::from(vec![
+        Span::raw(format!("{} ", proc.name)),
+        Span::styled(" Details ", tab_style(InfoTab::Details)),
+        Span::raw(" "),
+        Span::styled(" Files ", tab_style(InfoTab::Files)),
+        Span::raw(" "),
+        Span::styled(" Ancestry ", tab_style(InfoTab::Ancestry)),
+    ]);
+    let search_name = match state.info_tab {
+        InfoTab::Details => "environment",
+        InfoTab::Files => "files",
+        InfoTab::Ancestry => "ancestors",
+    };
+    let search = match state.keymap.keys(Action::Search).first() {
+        Some(key) => format!("{key}: search {search_name}  "),
+        None => String::new(),
+    };
+    let mut block = Block::default()
+        .borders(Borders::ALL)
+        .title(title)
+        .title_bottom(format!("tab: switch  ↑/↓ scroll  {search}esc: close"))
+        .border_type(BorderType::Rounded);
+    if state.info_searching || !state.info_search.is_empty() {
+        block = block.title_bottom(format!(" Search: {} ", state.info_search));
+    }
+
+    let max_width = lines.iter().map(|l| l.len()).max().unwrap_or_default() as u16 + 2;
+    let w = 60.max(max_width).min(f.area().width.saturating_sub(4));
+    let h = (lines.len() as u16 + 2).min(f.area().height.saturating_sub(2));
+    let p = Paragraph::new(lines.into_iter().map(Line::from).collect::<Vec<Line>>())
+        .style(
+            Style::default()
+                .bg(state.theme.popup_bg)
+                .fg(state.theme.popup_fg),
+        )
+        .scroll((state.info_scroll, 0))
+        .block(block);
     let rect = f
         .area()
         .centered(Constraint::Length(w), Constraint::Length(h));
@@ -243,67 +595,285 @@ fn draw_process_info(f: &mut Frame<'_>, proc: &MyProcess, parent: &str) {
     f.render_widget(p, rect);
 }
 
+fn files_lines(state: &State) -> Vec<String> {
+    let fds = match &state.open_files {
+        Some(Ok(fds)) => fds,
+        Some(Err(err)) => return vec![err.clone()],
+        None => return vec![],
+    };
+    let counts = openfiles::counts(fds)
+        .iter()
+        .map(|(kind, count)| format!("{kind}: {count}"))
+        .collect::<Vec<_>>()
+        .join("  ");
+    let mut lines = vec![
+        format!("Open file descriptors: {}", fds.len()),
+        counts,
+        String::new(),
+        format!(
+            "{:>5} {:<7} {:<6} {:<30} {:<30} {}",
+            "FD", "Type", "Proto", "Target/Local", "Remote", "State"
+        ),
+    ];
+    let search = state.info_search.to_lowercase();
+    lines.extend(
+        fds.iter()
+            .map(|fd| match &fd.socket {
+                Some(sock) => format!(
+                    "{:>5} {:<7} {:<6} {:<30} {:<30} {}",
+                    fd.fd, fd.kind, sock.protocol, sock.local, sock.remote, sock.state
+                ),
+                None => format!("{:>5} {:<7} {:<6} {}", fd.fd, fd.kind, "", fd.target),
+            })
+            .filter(|line| line.to_lowercase().contains(&search)),
+    );
+    lines
+}
+
+///chain from the root down to the process, with the memory of each
+fn ancestry_lines(proc: &MyProcess, state: &State) -> Vec<String> {
+    let mut chain = state.visible.ancestors(&proc.id());
+    chain.reverse();
+    chain.push(proc);
+    let mut lines = vec![format!(
+        "{:>8} {:>10} {:>10}  {}",
+        "PID", "Self", "Total", "Name"
+    )];
+    let search = state.info_search.to_lowercase();
+    lines.extend(
+        chain
+            .iter()
+            .enumerate()
+            .map(|(i, p)| {
+                let branch = match i {
+                    0 => String::new(),
+                    _ => format!("{}└", " ".repeat(i - 1)),
+                };
+                format!(
+                    "{:>8} {:>10} {:>10}  {branch}{}",
+                    p.pid,
+                    nice_size(p.memory),
+                    nice_size(p.total()),
+                    p.name
+                )
+            })
+            .filter(|line| line.to_lowercase().contains(&search)),
+    );
+    lines
+}
+
+fn details_lines(proc: &MyProcess, parent: &str, state: &State) -> Vec<String> {
+    let opt = |val: Option<u64>| val.map_or("n/a".to_string(), nice_size);
+    let mut lines = vec![format!("PID: {}", proc.pid), format!("Name: {}", proc.name)];
+    if !parent.is_empty() {
+        lines.push(format!("Parent PID: {:?}", proc.parent));
+        lines.push(format!("Parent Name: {}", parent));
+    }
+    lines.extend([
+        format!("CPU: {:>5.1}%", proc.cpu),
+        format!("Run Time: {}", nice_time(proc.run_time)),
+        format!("Memory (self):     {:>10}", nice_size(proc.memory)),
+        format!("Memory (children): {:>10}", nice_size(proc.children_memory)),
+        format!("Memory (total):    {:>10}", nice_size(proc.total())),
+    ]);
+    lines.push(format!(
+        "{:<26}{:>10} {:>10} {:>10}",
+        "", "Self", "Children", "Total"
+    ));
+    let fmt = |metric: Metric, val: f64| match metric.format(val) {
+        s if s.is_empty() => "0".to_string(),
+        s => s,
+    };
+    lines.extend(Metric::ALL.iter().map(|m| {
+        format!(
+            "{:<26}{:>10} {:>10} {:>10}",
+            m.label(),
+            fmt(*m, proc.usage(*m)),
+            fmt(*m, proc.children(*m)),
+            fmt(*m, proc.total_usage(*m))
+        )
+    }));
+    if let Some(details) = &state.info {
+        let mem = &details.memory;
+        lines.extend([
+            format!("State: {}", details.state),
+            format!("User: {}", details.user),
+            format!("Group: {}", details.group),
+            format!(
+                "Threads: {}",
+                details.threads.map_or("n/a".to_string(), |t| t.to_string())
+            ),
+            format!("Start Time: {}", nice_timestamp(details.start_time)),
+            format!(
+                "Open Files: {} (limit {})",
+                details
+                    .open_files
+                    .map_or("n/a".to_string(), |t| t.to_string()),
+                details
+                    .open_files_limit
+                    .map_or("n/a".to_string(), |t| t.to_string())
+            ),
+            format!("Executable: {}", details.exe),
+            format!("Working Dir: {}", details.cwd),
+            String::new(),
+            "Memory breakdown:".to_string(),
+            format!("  Resident: {:>10}", nice_size(mem.rss)),
+            format!("  Virtual:  {:>10}", nice_size(mem.virt)),
+            format!("  Shared:   {:>10}", opt(mem.shared)),
+            format!("  Text:     {:>10}", opt(mem.text)),
+            format!("  Data:     {:>10}", opt(mem.data)),
+            format!("  Swap:     {:>10}", opt(mem.swap)),
+            String::new(),
+            format!("Arguments ({}):", details.argv.len()),
+        ]);
+        lines.extend(
+            details
+                .argv
+                .iter()
+                .enumerate()
+                .map(|(i, arg)| format!("  [{i}] {arg}")),
+        );
+        if !details.limits.is_empty() {
+            lines.push(String::new());
+            lines.push("Limits:".to_string());
+            lines.extend(details.limits.iter().map(|l| format!("  {l}")));
+        }
+        let search = state.info_search.to_lowercase();
+        let env: Vec<&String> = details
+            .environ
+            .iter()
+            .filter(|e| e.to_lowercase().contains(&search))
+            .collect();
+        lines.push(String::new());
+        lines.push(format!(
+            "Environment ({}/{}):",
+            env.len(),
+            details.environ.len()
+        ));
+        lines.extend(env.iter().map(|e| format!("  {e}")));
+    } else {
+        lines.push(format!("Command Line: {}", proc.command));
+    }
+    lines
+}
+
 fn draw_filter(f: &mut Frame, state: &State) {
     let mut style = Style::default();
     if state.filtering {
-        style = style.bg(Color::Green).fg(Color::Black);
+        style = style.bg(state.theme.input_bg).fg(state.theme.input_fg);
     } else {
-        style = style.fg(Color::Green);
+        style = style.fg(state.theme.input_bg);
+    }
+    let top_height = get_cores_height(state) + get_disks_height(state) + 4;
+    let mut text = format!("Filter: {}", state.filter);
+    if let Some(proc_state) = state.visible.state_filter {
+        text = format!("State: {}  {text}", proc_state.label());
+    }
+    //invalid queries still match as plain text, but show why
+    if let Some(err) = state.visible.filter_error()
+        && !state.filtering
+    {
+        text = format!("{text} ({err})");
+        style = style.fg(state.theme.error);
     }
-    let top_height = get_cores_height(state) + 4;
-    let p = Paragraph::new(format!("Filter: {}", state.filter)).style(style);
+    let p = Paragraph::new(text).style(style);
     f.render_widget(
         p,
-        Rect::new(f.area().width.saturating_sub(40), top_height, 40, 1),
+        Rect::new(f.area().width.saturating_sub(60), top_height, 60, 1),
     );
 }
 fn draw_search(f: &mut Frame, state: &State) {
     let mut style = Style::default();
     if state.searching {
-        style = style.bg(Color::Green).fg(Color::Black);
+        style = style.bg(state.theme.input_bg).fg(state.theme.input_fg);
     }
-    let top_height = get_cores_height(state) + 4;
+    let top_height = get_cores_height(state) + get_disks_height(state) + 4;
     let p = Paragraph::new(format!("Search: {}", state.search)).style(style);
     f.render_widget(
         p,
         Rect::new(f.area().width.saturating_sub(40), top_height, 40, 1),
     );
 }
-fn draw_help(f: &mut Frame) {
-    let help = r#"↑/↓ j/k    Scroll
-←/→  h/l   Change column to sort
-s          Sort Asc/Desc/None
-q          Exit
-z          Hide/show zero memory
-Home       Go to first row
-End        Go to last row
-g          Start GUI mode
-f          Filter processes
-F          Follow process
-/          Search for process
-c          Hide CPU cores
-+/-        Change update interval
-command line arguments for modes:
--g         Graphical mode
--t         Terminal mode (default)
--s <FILE>  save svg of memory graph
--o         output to stdout and exit
-
-                                             "#;
-
-    let p = Paragraph::new(help)
-        .style(Style::default().bg(Color::Yellow).fg(Color::Black))
+fn draw_view_name(f: &mut Frame, state: &State, name: &str) {
+    let style = Style::default()
+        .bg(state.theme.input_bg)
+        .fg(state.theme.input_fg);
+    let top_height = get_cores_height(state) + get_disks_height(state) + 4;
+    let p = Paragraph::new(format!("Save view as: {name}")).style(style);
+    f.render_widget(
+        p,
+        Rect::new(f.area().width.saturating_sub(40), top_height, 40, 1),
+    );
+}
+fn draw_help(f: &mut Frame, state: &State) {
+    let keys = |action: Action| {
+        let keys: Vec<String> = state
+            .keymap
+            .keys(action)
+            .iter()
+            .map(|k| k.to_string())
+            .collect();
+        keys.join("/")
+    };
+    let width = Action::ALL
+        .iter()
+        .map(|a| keys(*a).len())
+        .max()
+        .unwrap_or_default();
+    let mut lines: Vec<String> = Action::ALL
+        .iter()
+        .filter(|action| !state.keymap.keys(**action).is_empty())
+        .map(|action| format!("{:<width$} {}", keys(*action), action.description()))
+        .collect();
+    lines.extend([
+        format!("{:<width$} Switch to saved view, 0 for default", "1-9"),
+        "command line arguments for modes:".to_string(),
+        "-g         Graphical mode".to_string(),
+        "-t         Terminal mode (default)".to_string(),
+        "-s <FILE>  save svg of memory graph".to_string(),
+        "-o         output to stdout and exit".to_string(),
+        "-m <PID>   output memory map of process and exit".to_string(),
+    ]);
+
+    //two columns if it does not fit
+    let col_width = lines
+        .iter()
+        .map(|l| l.chars().count())
+        .max()
+        .unwrap_or_default()
+        + 2;
+    let area = f.area();
+    if lines.len() as u16 + 2 > area.height && area.width as usize >= col_width * 2 + 2 {
+        let half = lines.len().div_ceil(2);
+        lines = (0..half)
+            .map(|i| {
+                let right = lines.get(half + i).cloned().unwrap_or_default();
+                format!("{:<col_width$}{right}", lines[i])
+            })
+            .collect();
+    }
+
+    let w = (lines
+        .iter()
+        .map(|l| l.chars().count())
+        .max()
+        .unwrap_or_default() as u16
+        + 2)
+    .min(area.width);
+    let h = (lines.len() as u16 + 2).min(area.height);
+    let p = Paragraph::new(lines.into_iter().map(Line::from).collect::<Vec<Line>>())
+        .style(
+            Style::default()
+                .bg(state.theme.popup_bg)
+                .fg(state.theme.popup_fg),
+        )
         .block(
             Block::default()
                 .borders(Borders::ALL)
                 .title("Help")
                 .border_type(BorderType::Rounded),
         );
-    let x = f.area().width / 3;
-    let y = f.area().height.saturating_sub(help.lines().count() as u16) / 3;
-    let w = 50.min(f.area().width.saturating_sub(x));
-    let h = 20.min(f.area().height.saturating_sub(y));
-    let rect = Rect::new(x, y, w, h);
+    let rect = area.centered(Constraint::Length(w), Constraint::Length(h));
     f.render_widget(Clear, rect);
     f.render_widget(p, rect);
 }
@@ -314,13 +884,14 @@ fn draw_top(f: &mut Frame, state: &State) {
     let cpu_height = get_cores_height(state);
 
     //draw cpus
-    if state.config.tui.show_cpu_per_core {
+    if show_cores(state) {
         draw_cpu_cores(f, state);
     }
 
     let max_cpu = totals.cpus.iter().copied().fold(0., f32::max) / 100.;
     draw_cpu_summary(
         f,
+        &state.theme,
         Rect::new(0, cpu_height, 48, 1),
         totals.cpu_avg / 100.,
         max_cpu,
@@ -328,11 +899,68 @@ fn draw_top(f: &mut Frame, state: &State) {
         &format!("Cpu x{}:", totals.cpu_count),
     );
 
-    draw_mem(totals, f, cpu_height + 1);
+    draw_mem(totals, f, &state.theme, cpu_height + 1);
     draw_uptime(f, state, cpu_height + 2);
     draw_network(state, f, cpu_height + 3);
-    draw_process_interval(f, state, cpu_height + 4);
-    draw_commands(f, cpu_height + 5);
+    let disks_height = get_disks_height(state);
+    if state.config.tui.show_disks {
+        draw_disks(f, state, cpu_height + 4);
+    }
+    draw_process_interval(f, state, cpu_height + disks_height + 4);
+    draw_commands(f, state, cpu_height + disks_height + 5);
+}
+
+fn draw_disks(f: &mut Frame<'_>, state: &State, y: u16) {
+    let name_width = state
+        .disks
+        .iter()
+        .map(|d| d.mount_point.chars().count().max(d.name.chars().count()))
+        .max()
+        .unwrap_or_default()
+        .min(30);
+    let fit = |name: &str| name.chars().take(name_width).collect::<String>();
+    //the last row counts the disks which do not fit
+    let shown = if state.disks.len() > MAX_DISK_ROWS {
+        MAX_DISK_ROWS - 1
+    } else {
+        state.disks.len()
+    };
+    if shown < state.disks.len() {
+        let y = y + shown as u16;
+        let more = format!("+{} more disks", state.disks.len() - shown);
+        f.render_widget(Line::from(more), Rect::new(0, y, f.area().width, 1));
+    }
+    for (i, disk) in state.disks.iter().take(shown).enumerate() {
+        let y = y + i as u16;
+        if y >= f.area().height {
+            break;
+        }
+        let ratio = if disk.total == 0 {
+            0.
+        } else {
+            disk.used() as f32 / disk.total as f32
+        };
+        let details = format!(
+            "{:<name_width$} {:>7}/{:<7}{:>6.1}%",
+            fit(&disk.mount_point),
+            nice_size_g(disk.used()),
+            nice_size_g(disk.total),
+            ratio * 100.,
+        );
+        let len = details.chars().count() as u16;
+        f.render_widget(Line::from(details), Rect::new(0, y, len, 1));
+        gauge(f, &state.theme, Rect::new(len + 1, y, 25, 1), ratio, "");
+        let io = format!(
+            "{:<name_width$} R {:>9}/s W {:>9}/s",
+            fit(&disk.name),
+            nice_size_ops(disk.read_per_sec, true, false),
+            nice_size_ops(disk.written_per_sec, true, false)
+        );
+        f.render_widget(
+            Line::from(io),
+            Rect::new(len + 27, y, f.area().width.saturating_sub(len + 27), 1),
+        );
+    }
 }
 
 fn draw_uptime(f: &mut Frame<'_>, state: &State, cpu_height: u16) {
@@ -348,29 +976,89 @@ fn draw_cpu_cores(f: &mut Frame<'_>, state: &State) {
         if y >= f.area().height {
             break;
         }
-        draw_sub_cpu(
-            f,
-            Rect::new(x, y, width, 1),
-            *cp / 100.,
-            &format!("{}", i + 1),
-        );
+        let rect = Rect::new(x, y, width, 1);
+        match &state.affinity {
+            //show which cores the process may run on while editing affinity
+            Some(edit) => {
+                let allowed = edit.cores.get(i).copied().unwrap_or_default();
+                let mut style = if allowed {
+                    Style::default().fg(state.theme.collapsed)
+                } else {
+                    Style::default().fg(state.theme.gauge_empty)
+                };
+                if edit.core == i {
+                    style = style.add_modifier(Modifier::REVERSED);
+                }
+                let title = format!(
+                    "{}{:>4} {:>5.1}%",
+                    if allowed { "✓" } else { "✗" },
+                    i + 1,
+                    cp
+                );
+                gauge(
+                    f,
+                    &state.theme,
+                    rect,
+                    *cp / 100.,
+                    Span::styled(title, style),
+                );
+            }
+            None => draw_sub_cpu(f, &state.theme, rect, *cp / 100., &format!("{}", i + 1)),
+        }
     }
 }
 
-fn draw_commands(f: &mut Frame<'_>, y: u16) {
-    let commands = Block::default().title(
-        "?: help  s: Sort  c: CPU  enter: info  f: filter  /:search  F: follow  ctrl+k: kill  m: collapse children"
-            .to_string(),
-    );
+fn draw_commands(f: &mut Frame<'_>, state: &State, y: u16) {
+    const COMMANDS: [(Action, &str); 13] = [
+        (Action::Help, "help"),
+        (Action::SortCycle, "Sort"),
+        (Action::ToggleCores, "CPU"),
+        (Action::ToggleDisks, "disks"),
+        (Action::Info, "info"),
+        (Action::Filter, "filter"),
+        (Action::Search, "search"),
+        (Action::Follow, "follow"),
+        (Action::Kill, "kill"),
+        (Action::Stop, "stop"),
+        (Action::Continue, "continue"),
+        (Action::Affinity, "affinity"),
+        (Action::Collapse, "collapse children"),
+    ];
+    //first key of each action, if bound
+    let commands: Vec<String> = COMMANDS
+        .iter()
+        .filter_map(|(action, name)| {
+            let key = state.keymap.keys(*action).first().copied()?;
+            Some(format!("{key}: {name}"))
+        })
+        .collect();
+    let commands = Block::default().title(commands.join("  "));
 
     f.render_widget(commands, Rect::new(0, y, f.area().width, 1));
 }
 
 fn draw_process_interval(f: &mut Frame<'_>, state: &State, cpu_height: u16) {
+    let forks = match state.churn.forks {
+        Some(forks) => format!(" Forks: {forks}"),
+        None => String::new(),
+    };
+    let view = match &state.view {
+        Some(name) => format!("   View: {name}"),
+        None => String::new(),
+    };
+    let family = match state.visible.family {
+        Some(id) => match state.procs.get(&id) {
+            Some(proc) => format!("   Family of: {} ({})", proc.name, proc.pid),
+            None => format!("   Family of: {} (exited)", id.pid),
+        },
+        None => String::new(),
+    };
     let threads = Block::default().title(format!(
-        "Processes: {}   Interval: {}s",
+        "Processes: {}   Interval: {}s   Started: {} Exited: {}{forks}{view}{family}",
         state.procs.len(),
-        state.config.tui.update_interval
+        state.config.tui.update_interval,
+        state.churn.started,
+        state.churn.exited,
     ));
     f.render_widget(threads, Rect::new(0, cpu_height, f.area().width, 1));
 }
@@ -397,8 +1085,11 @@ fn draw_network(state: &State, f: &mut Frame<'_>, y: u16) {
     f.render_widget(line, Rect::new(0, y, f.area().width, 1));
 }
 
-fn draw_mem(totals: &Totals, f: &mut Frame, y: u16) {
-    let col = get_gradient(totals.memory_procs as f32 / totals.memory_total as f32);
+fn draw_mem(totals: &Totals, f: &mut Frame, theme: &Theme, y: u16) {
+    let col = get_gradient(
+        theme,
+        totals.memory_procs as f32 / totals.memory_total as f32,
+    );
     let line = Line::from(vec![
         Span::raw("Memory: "),
         Span::raw(format!(
@@ -423,18 +1114,19 @@ fn draw_mem(totals: &Totals, f: &mut Frame, y: u16) {
 
     gauge(
         f,
+        theme,
         rect,
         totals.memory_used as f32 / totals.memory_total as f32,
         "",
     )
 }
 
-fn gauge<T>(f: &mut Frame, rect: Rect, percentage: f32, title: T)
+fn gauge<T>(f: &mut Frame, theme: &Theme, rect: Rect, percentage: f32, title: T)
 where
     T: Into<Line<'static>>,
 {
     //go from yellow to red depending on value by exponential gradient
-    let col = get_gradient(percentage);
+    let col = get_gradient(theme, percentage);
     let percentage = percentage.clamp(0., 1.);
 
     let gr = LineGauge::default()
@@ -442,27 +1134,38 @@ where
         .filled_symbol("■")
         .unfilled_symbol("━")
         .filled_style(Style::new().fg(col))
-        .unfilled_style(Style::new().fg(Color::DarkGray))
+        .unfilled_style(Style::new().fg(theme.gauge_empty))
         .ratio(percentage as f64);
 
     f.render_widget(gr, rect);
 }
 
-fn get_gradient(val: f32) -> Color {
-    let red = 255;
-    let green = (255. * (1. - val.clamp(0., 1.).powf(2.0))) as u8;
-
-    Color::Rgb(red, green, 0)
+fn get_gradient(theme: &Theme, val: f32) -> Color {
+    theme.gradient(val.clamp(0., 1.).powf(2.0))
 }
 
-fn draw_sub_cpu(f: &mut Frame, rect: Rect, cpu: f32, title: &str) {
-    gauge(f, rect, cpu, format!("{title:>6} {:>5.1}%", cpu * 100.));
+fn draw_sub_cpu(f: &mut Frame, theme: &Theme, rect: Rect, cpu: f32, title: &str) {
+    gauge(
+        f,
+        theme,
+        rect,
+        cpu,
+        format!("{title:>6} {:>5.1}%", cpu * 100.),
+    );
 }
-fn draw_cpu_summary(f: &mut Frame, rect: Rect, cpu: f32, max_cpu: f32, sum_cpu: f32, title: &str) {
+fn draw_cpu_summary(
+    f: &mut Frame,
+    theme: &Theme,
+    rect: Rect,
+    cpu: f32,
+    max_cpu: f32,
+    sum_cpu: f32,
+    title: &str,
+) {
     let mut details = vec![Span::raw(format!("{title:>6} "))];
     //only add for summary cpu:
 
-    let col_avg = get_gradient(cpu);
+    let col_avg = get_gradient(theme, cpu);
     details.push(Span::raw(" Avg:"));
     details.push(Span::styled(
         format!("{:>5.1}%", cpu * 100.),
@@ -470,12 +1173,12 @@ fn draw_cpu_summary(f: &mut Frame, rect: Rect, cpu: f32, max_cpu: f32, sum_cpu:
     ));
 
     details.push(Span::raw(" Max:".to_string()));
-    let col_max = get_gradient(max_cpu);
+    let col_max = get_gradient(theme, max_cpu);
     details.push(Span::styled(
         format!("{:>5.1}%", max_cpu * 100.),
         Style::default().fg(col_max),
     ));
-    let col_sum = get_gradient(sum_cpu);
+    let col_sum = get_gradient(theme, sum_cpu);
     details.push(Span::raw(" Sum:"));
     details.push(Span::styled(
         format!("{:>6.1}%", sum_cpu * 100.),
@@ -487,75 +1190,106 @@ fn draw_cpu_summary(f: &mut Frame, rect: Rect, cpu: f32, max_cpu: f32, sum_cpu:
     let mut rect = rect;
     rect.x += len + 1;
     rect.width = 25;
-    gauge(f, rect, cpu, "");
+    gauge(f, theme, rect, cpu, "");
 }
 
 fn draw_table(f: &mut Frame, state: &State, tablestate: &mut TableState) {
-    let top_height = get_cores_height(state) + 6;
-
-    let header_cells = Row::new(
-        [
-            "Command", "Name", "PID", "Self", "Children", "Total", "CPU", "Disk",
-        ]
-        .iter()
-        .enumerate()
-        .map(|(i, h)| {
-            let mut name = h.to_string();
-            let mut style = Style::default().fg(Color::Black).bg(Color::LightBlue);
-
-            if i == state.visible.sort_col {
-                style = Style::default().fg(Color::White).bg(Color::LightRed);
-
-                match state.visible.sort_type {
-                    SortType::Ascending => {
-                        name.push_str(" ↑");
-                    }
+    let columns = &state.visible.columns;
+    let header_cells = Row::new(columns.iter().map(|col| {
+        let mut name = col.header();
+        //metrics include all descendants
+        if state.visible.tree_totals && matches!(col, Column::Metric(_)) {
+            name.push('Σ');
+        }
+        let mut style = Style::default()
+            .fg(state.theme.header_fg)
+            .bg(state.theme.header_bg);
+
+        if *col == state.visible.sort_col {
+            style = Style::default()
+                .fg(state.theme.sort_fg)
+                .bg(state.theme.sort_bg);
+
+            match state.visible.sort_type {
+                SortType::Ascending => {
+                    name.push_str(" ↑");
+                }
 
-                    SortType::Descending => {
-                        name.push_str(" ↓");
-                    }
-                    _ => {}
+                SortType::Descending => {
+                    name.push_str(" ↓");
                 }
+                _ => {}
             }
-            let name = if (2..).contains(&i) {
-                format!("{name:>10}")
-            } else {
-                name
-            };
+        }
+        if *col == Column::Command && state.visible.tree_mode {
+            name.push_str(" (tree)");
+        }
+        let name = if col.right_align() {
+            format!("{name:>width$}", width = col.width() as usize)
+        } else {
+            name
+        };
 
-            Cell::from(name).style(style)
-        }),
-    )
-    .style(Style::default().bg(Color::LightBlue));
+        Cell::from(name).style(style)
+    }))
+    .style(Style::default().bg(state.theme.header_bg));
 
     let rows: Vec<Row> = state
         .visible
-        .procs()
+        .rows()
         .iter()
-        .map(|f| {
-            let pid = f[2].parse::<usize>().unwrap();
-
-            Row::new(f.iter().enumerate().map(|(i, c)| {
-                let mut val = match i {
-                    2.. => format!("{c:>10}"),
-                    _ => c.to_string(),
+        .map(|row| {
+            let id = &row.proc.id();
+            Row::new(columns.iter().map(|col| {
+                let c = state.visible.format(row, *col);
+                let mut val = if col.right_align() {
+                    format!("{c:>width$}", width = col.width() as usize)
+                } else {
+                    c.to_string()
                 };
 
-                let mut style = if state.top5memory.contains(&pid) && (i == 3 || i == 1) {
-                    Style::default().fg(Color::LightRed)
+                let mut style = if state.top5memory.contains(id)
+                    && matches!(col, Column::Memory | Column::Name)
+                {
+                    Style::default().fg(state.theme.top_memory)
                 } else {
                     Style::default()
                 };
-                if i == 0
-                    && state.visible.hide_children.contains(&pid)
-                    && state.visible.sort_col == 0
+                if *col == Column::Command
+                    && state.visible.hide_children.contains(id)
+                    && state.visible.is_tree()
                 {
-                    style = Style::default().fg(Color::LightGreen);
+                    style = Style::default().fg(state.theme.collapsed);
                     val = format!("{val} [Collapsed]");
                 }
 
-                if state.top5cpu.contains(&pid) && (i == 6 || i == 1) {
-                    style = Style::default().fg(Color::Magenta);
+                if state.top5cpu.contains(id)
+                    && matches!(col, Column::Metric(Metric::Cpu) | Column::Name)
+                {
+                    style = Style::default().fg(state.theme.top_cpu);
+                }
+
+                //zombies and processes stuck in uninterruptible sleep need attention
+                if let Some(proc) = state.procs.get(id) {
+                    match proc.state {
+                        ProcState::Zombie => style = Style::default().fg(state.theme.zombie),
+                        ProcState::DiskSleep => style = Style::default().fg(state.theme.disk_sleep),
+                        _ => {}
+                    }
+                }
+
+                if state.visible.is_context(id) {
+                    style = style.add_modifier(Modifier::DIM);
+                }
+                if row.hidden > 0 {
+                    style = style.add_modifier(Modifier::ITALIC);
+                }
+
+                if let Some(proc) = state.exited.get(id) {
+                    style = Style::default().fg(state.theme.exited);
+                    if *col == Column::Command {
+                        val = format!("{val} [Exited, lived {}]", nice_time(proc.run_time));
+                    }
                 }
 
                 Cell::from(val).style(style)
@@ -564,230 +1298,662 @@ fn draw_table(f: &mut Frame, state: &State, tablestate: &mut TableState) {
         })
         .collect();
 
-    let command_width = (f.area().width.max(85) - 85).max(25);
-    let widths = [
-        Constraint::Min(command_width),
-        Constraint::Length(15),
-        Constraint::Length(10),
-        Constraint::Length(10),
-        Constraint::Length(10),
-        Constraint::Length(10),
-        Constraint::Length(10),
-        Constraint::Length(10),
-    ];
+    let widths = column_widths(state, f.area().width);
     let highlight = match state.selected {
-        Selected::Index(_) => Color::LightYellow,
-        Selected::Proc(_) => Color::LightRed,
+        Selected::Index(_) => state.theme.selected_bg,
+        Selected::Proc(_) => state.theme.followed_bg,
     };
     let t = Table::new(rows, widths)
         .header(header_cells)
-        .row_highlight_style(Style::default().bg(highlight).fg(Color::Black));
+        .row_highlight_style(Style::default().bg(highlight).fg(state.theme.selected_fg));
 
-    let mut rect = f.area();
+    f.render_stateful_widget(t, table_area(state, f.area()), tablestate);
+}
+
+///below the summary, the first line is the header
+fn table_area(state: &State, area: Rect) -> Rect {
+    let top_height = get_cores_height(state) + get_disks_height(state) + 6;
+    let mut rect = area;
     rect.y += top_height;
     rect.height = rect.height.saturating_sub(top_height);
-    f.render_stateful_widget(t, rect, tablestate);
+    rect
+}
+
+fn column_widths(state: &State, width: u16) -> Vec<Constraint> {
+    //command takes up the remaining width
+    let columns = &state.visible.columns;
+    let fixed: u16 = columns
+        .iter()
+        .filter(|c| **c != Column::Command)
+        .map(|c| c.width())
+        .sum();
+    let command_width = width.saturating_sub(fixed).max(Column::Command.width());
+    columns
+        .iter()
+        .map(|c| match c {
+            Column::Command => Constraint::Min(command_width),
+            _ => Constraint::Length(c.width()),
+        })
+        .collect()
+}
+
+///same layout as the table uses, to find the column under the mouse
+fn column_areas(state: &State, area: Rect) -> Vec<Rect> {
+    Layout::horizontal(column_widths(state, area.width))
+        .spacing(1)
+        .split(area)
+        .to_vec()
+}
+
+fn get_disks_height(state: &State) -> u16 {
+    if state.config.tui.show_disks {
+        state.disks.len().min(MAX_DISK_ROWS) as u16
+    } else {
+        0
+    }
 }
 
 fn get_cores_height(state: &State) -> u16 {
-    if state.config.tui.show_cpu_per_core {
-        state.totals.cpus.len() as u16 / 4
+    if show_cores(state) {
+        state.totals.cpus.len().div_ceil(4) as u16
     } else {
         0
     }
 }
 
-fn handle_input(done: &mut bool, state: &mut State) {
-    if let Ok(true) = event::poll(Duration::from_millis(50))
-        && let Ok(Event::Key(key)) = event::read()
-        && key.kind == KeyEventKind::Press
-    {
-        if state.filtering {
-            match key.code {
-                KeyCode::Esc => {
-                    state.filtering = false;
-                    state.filter.clear();
-                }
-                KeyCode::Enter => state.filtering = false,
-                KeyCode::Char(c) => state.filter.push(c),
-                KeyCode::Backspace => {
-                    let _ = state.filter.pop();
-                }
-                _ => {}
+///cores are always shown while editing affinity
+fn show_cores(state: &State) -> bool {
+    state.config.tui.show_cpu_per_core || state.affinity.is_some()
+}
+
+fn handle_input(done: &mut bool, state: &mut State, offset: usize) {
+    if let Ok(true) = event::poll(Duration::from_millis(50)) {
+        match event::read() {
+            Ok(Event::Key(key)) if key.kind == KeyEventKind::Press => handle_key(key, done, state),
+            Ok(Event::Mouse(mouse)) => handle_mouse(mouse, state, offset),
+            _ => {}
+        }
+    }
+}
+
+///clicks select rows, sort by headers, collapse in the tree and toggle the cores
+fn handle_mouse(mouse: MouseEvent, state: &mut State, offset: usize) {
+    let Ok((width, height)) = terminal::size() else {
+        return;
+    };
+    let area = table_area(state, Rect::new(0, 0, width, height));
+    let (x, y) = (mouse.column, mouse.row);
+    let cores_height = get_cores_height(state);
+
+    if let Some(edit) = &mut state.affinity {
+        //cores are shown in rows of 4
+        if mouse.kind == MouseEventKind::Down(MouseButton::Left) && y < cores_height {
+            //leftover columns on the right belong to the last core of the row
+            let column = (x / (width / 4).max(1)).min(3);
+            let core = y as usize * 4 + column as usize;
+            if let Some(allowed) = edit.cores.get_mut(core) {
+                *allowed = !*allowed;
+                edit.core = core;
             }
-            state.visible.set_filter(state.filter.clone());
-        } else if state.searching {
-            match key.code {
-                KeyCode::Esc | KeyCode::Enter => {
-                    state.searching = false;
-                    state.search.clear();
-                }
-                KeyCode::Char(c) => {
-                    state.search.push(c);
-                    state.update_search();
-                }
-                KeyCode::Backspace => {
-                    let _ = state.search.pop();
-                    state.update_search();
+        }
+        return;
+    }
+    let popup = state.show_help
+        || state.show_info.is_some()
+        || state.show_kill
+        || state.threads.is_some()
+        || state.smaps.is_some()
+        || state.column_picker.is_some();
+    if popup {
+        return;
+    }
+
+    match mouse.kind {
+        MouseEventKind::ScrollDown => move_by(state, 3),
+        MouseEventKind::ScrollUp => move_by(state, -3),
+        //per core gauges, or the cpu summary below them
+        MouseEventKind::Down(MouseButton::Left) if y <= cores_height => {
+            state.config.tui.show_cpu_per_core = !state.config.tui.show_cpu_per_core;
+        }
+        MouseEventKind::Down(MouseButton::Left) if y >= area.y && y < area.bottom() => {
+            let columns = column_areas(state, area);
+            let Some(i) = columns.iter().position(|c| x >= c.x && x < c.right()) else {
+                return;
+            };
+            let col = state.visible.columns[i];
+            if y == area.y {
+                //header sorts, or cycles the direction if already sorted
+                if col == state.visible.sort_col {
+                    state.visible.sort_cycle();
+                } else {
+                    state.visible.sort_col = col;
+                    state.visible.sort_type = if col == Column::Command {
+                        SortType::None
+                    } else {
+                        SortType::Descending
+                    };
                 }
-                _ => {}
+                state.sort();
+                return;
             }
-            state.visible.set_filter(state.filter.clone());
-        } else if state.show_help {
-            match key.code {
-                KeyCode::Esc | KeyCode::Char('?') | KeyCode::F(1) => state.show_help = false,
-                _ => {}
+            let index = offset + (y - area.y - 1) as usize;
+            let Some(row) = state.visible.rows().get(index) else {
+                return;
+            };
+            let id = row.proc.id();
+            //roots have no prefix, their first cell toggles them
+            let glyph_width = row.prefix.chars().count().max(1) as u16;
+            let on_glyph = col == Column::Command
+                && row.hidden == 0
+                && x < columns[i].x + glyph_width
+                && state.visible.is_tree();
+            state.selected = match state.selected {
+                Selected::Index(_) => Selected::Index(index),
+                Selected::Proc(_) => Selected::Proc(id),
+            };
+            if on_glyph {
+                state.visible.hide_children_invert(id);
+                state.sort();
             }
-        } else if state.show_info.is_some() {
-            match key.code {
-                KeyCode::Esc | KeyCode::Enter => state.show_info = None,
-                _ => {}
+        }
+        _ => {}
+    }
+}
+
+fn handle_key(key: KeyEvent, done: &mut bool, state: &mut State) {
+    let action = state.keymap.action(&key);
+    //arrows always work in popups, other keys follow the keymap
+    let nav = match key.code {
+        KeyCode::Up => Some(Action::Up),
+        KeyCode::Down => Some(Action::Down),
+        KeyCode::Left => Some(Action::SortLeft),
+        KeyCode::Right => Some(Action::SortRight),
+        KeyCode::PageUp => Some(Action::PageUp),
+        KeyCode::PageDown => Some(Action::PageDown),
+        KeyCode::Home => Some(Action::First),
+        _ => action,
+    };
+    if state.filtering {
+        match key.code {
+            KeyCode::Esc => {
+                state.filtering = false;
+                state.filter.clear();
             }
-        } else if state.show_kill {
-            match key.code {
-                KeyCode::Down | KeyCode::Char('j') => {
-                    state.kill_signal = (state.kill_signal + 1).min(20)
-                }
-                KeyCode::Char(c) if c.is_ascii_digit() => {
-                    state.kill_signal = c as usize - '0' as usize;
-                }
-                KeyCode::Up | KeyCode::Char('k') => {
-                    state.kill_signal = state.kill_signal.saturating_sub(1)
-                }
-                KeyCode::Esc => state.show_kill = false,
-                KeyCode::Enter => {
-                    if state.kill_signal == 0 {
-                        //cancel
-                    } else {
-                        //send signal
-                        let signal = state.kill_signal as i32;
-                        if let Some(proc) = &state.kill_process {
-                            let _ = Command::new("kill")
-                                .arg(format!("-{signal}"))
-                                .arg(format!("{}", proc.pid))
-                                .output();
-                        }
-                    }
-                    state.show_kill = false;
-                }
-                _ => {}
+            KeyCode::Enter => state.filtering = false,
+            KeyCode::Char(c) => state.filter.push(c),
+            KeyCode::Backspace => {
+                let _ = state.filter.pop();
             }
-        } else {
-            match key.code {
-                KeyCode::Char('q') => *done = true,
-                KeyCode::Char('s') => {
-                    state.visible.sort_cycle();
-                    state.sort();
-                }
-                KeyCode::Char('c') => {
-                    if key.modifiers.contains(KeyModifiers::CONTROL) {
-                        *done = true
-                    } else {
-                        state.config.tui.show_cpu_per_core = !state.config.tui.show_cpu_per_core;
-                    }
-                }
-                KeyCode::Char('f') => {
-                    state.filtering = !state.filtering;
-                }
-                KeyCode::Char('m') => {
-                    if state.visible.sort_col == 0
-                        && let Some(proc) = process_at_selected(state)
-                    {
-                        state.visible.hide_children_invert(proc.pid);
-                    }
-                }
-                KeyCode::Char('F') => match state.selected {
-                    Selected::Index(_) => {
-                        if let Some(proc) = process_at_selected(state) {
-                            state.selected = Selected::Proc(proc.pid);
-                        }
-                    }
-                    Selected::Proc(pid) => {
-                        state.selected = Selected::Index(
-                            state
-                                .visible
-                                .procs()
-                                .iter()
-                                .position(|p| p[2].parse::<usize>().unwrap_or_default() == pid)
-                                .unwrap_or(0),
-                        );
-                    }
-                },
-                KeyCode::Char('/') => {
-                    state.searching = true;
-                }
-                KeyCode::Char('z') => state.visible.hidezeros = !state.visible.hidezeros,
-                KeyCode::Char('?') | KeyCode::F(1) => state.show_help = !state.show_help,
-                KeyCode::Char('g') => {
-                    *done = true;
-                    state.start_gui = true
-                }
-                KeyCode::Char('k') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                    state.show_kill = !state.show_kill;
-                    let proc = process_at_selected(state);
-                    state.kill_process = proc;
-                }
-                KeyCode::Down | KeyCode::Char('j') => move_by(state, 1),
-                KeyCode::Up | KeyCode::Char('k') => move_by(state, -1),
-                KeyCode::PageDown => move_by(state, 20),
-                KeyCode::PageUp => move_by(state, -20),
-                KeyCode::Home => move_by(state, isize::MIN),
-                KeyCode::End => move_by(state, isize::MAX),
-                KeyCode::Enter => {
-                    if let Some(proc) = process_at_selected(state) {
-                        state.show_info = Some(proc.pid);
-                    }
+            _ => {}
+        }
+        state.visible.set_filter(state.filter.clone());
+    } else if let Some(name) = &mut state.naming_view {
+        match key.code {
+            KeyCode::Esc => state.naming_view = None,
+            KeyCode::Enter if !name.is_empty() => {
+                let view = state.visible.to_view(name.clone());
+                //replace view with the same name
+                state.config.views.retain(|v| v.name != view.name);
+                state.view = Some(view.name.clone());
+                state.config.views.push(view);
+                state.naming_view = None;
+            }
+            KeyCode::Char(c) => name.push(c),
+            KeyCode::Backspace => {
+                let _ = name.pop();
+            }
+            _ => {}
+        }
+    } else if state.searching {
+        match key.code {
+            KeyCode::Esc | KeyCode::Enter => {
+                state.searching = false;
+                state.search.clear();
+            }
+            KeyCode::Char(c) => {
+                state.search.push(c);
+                state.update_search();
+            }
+            KeyCode::Backspace => {
+                let _ = state.search.pop();
+                state.update_search();
+            }
+            _ => {}
+        }
+        state.visible.set_filter(state.filter.clone());
+    } else if state.show_help {
+        if key.code == KeyCode::Esc || action == Some(Action::Help) {
+            state.show_help = false;
+        }
+    } else if state.info_searching {
+        match key.code {
+            KeyCode::Esc => {
+                state.info_searching = false;
+                state.info_search.clear();
+            }
+            KeyCode::Enter => state.info_searching = false,
+            KeyCode::Char(c) => state.info_search.push(c),
+            KeyCode::Backspace => {
+                let _ = state.info_search.pop();
+            }
+            _ => {}
+        }
+        state.info_scroll = 0;
+    } else if state.show_info.is_some() {
+        match key.code {
+            KeyCode::Esc | KeyCode::Enter => {
+                state.show_info = None;
+                state.info = None;
+                state.open_files = None;
+                state.info_search.clear();
+            }
+            KeyCode::Tab | KeyCode::Right => {
+                state.info_tab = match state.info_tab {
+                    InfoTab::Details => InfoTab::Files,
+                    InfoTab::Files => InfoTab::Ancestry,
+                    InfoTab::Ancestry => InfoTab::Details,
+                };
+                state.info_scroll = 0;
+                state.info_search.clear();
+                state.load_open_files();
+            }
+            KeyCode::Left => {
+                state.info_tab = match state.info_tab {
+                    InfoTab::Details => InfoTab::Ancestry,
+                    InfoTab::Files => InfoTab::Details,
+                    InfoTab::Ancestry => InfoTab::Files,
+                };
+                state.info_scroll = 0;
+                state.info_search.clear();
+                state.load_open_files();
+            }
+            _ if nav == Some(Action::Down) => {
+                state.info_scroll = (state.info_scroll + 1).min(state.info_scroll_max())
+            }
+            _ if nav == Some(Action::Up) => state.info_scroll = state.info_scroll.saturating_sub(1),
+            _ if nav == Some(Action::PageDown) => {
+                state.info_scroll = state
+                    .info_scroll
+                    .saturating_add(20)
+                    .min(state.info_scroll_max())
+            }
+            _ if nav == Some(Action::PageUp) => {
+                state.info_scroll = state.info_scroll.saturating_sub(20)
+            }
+            _ if nav == Some(Action::First) => state.info_scroll = 0,
+            _ if action == Some(Action::Search) => state.info_searching = true,
+            _ => {}
+        }
+    } else if state.show_kill {
+        match key.code {
+            _ if nav == Some(Action::Down) => state.kill_signal = (state.kill_signal + 1).min(20),
+            KeyCode::Char(c) if c.is_ascii_digit() => {
+                state.kill_signal = c as usize - '0' as usize;
+            }
+            _ if nav == Some(Action::Up) => state.kill_signal = state.kill_signal.saturating_sub(1),
+            KeyCode::Char('t') => state.signal_tree = !state.signal_tree,
+            KeyCode::Esc => state.show_kill = false,
+            KeyCode::Enter => {
+                if state.kill_signal == 0 {
+                    //cancel
+                } else if let Some(proc) = &state.kill_process {
+                    state.send_signal(proc.id(), &state.kill_signal.to_string(), state.signal_tree);
                 }
-                KeyCode::Left | KeyCode::Char('h') => {
-                    state.visible.sort_col = state.visible.sort_col.saturating_sub(1);
-                    if state.visible.sort_col == 0 {
-                        state.visible.sort_type = SortType::None;
-                    }
-                    state.sort();
+                state.show_kill = false;
+            }
+            _ => {}
+        }
+    } else if let Some(view) = &mut state.threads {
+        let len = view.threads.as_ref().map_or(0, |t| t.len());
+        match key.code {
+            KeyCode::Esc | KeyCode::Enter => state.threads = None,
+            _ if nav == Some(Action::Down) => {
+                view.scroll = (view.scroll + 1).min(len.saturating_sub(1))
+            }
+            _ if nav == Some(Action::Up) => view.scroll = view.scroll.saturating_sub(1),
+            _ if nav == Some(Action::PageDown) => {
+                view.scroll = (view.scroll + 20).min(len.saturating_sub(1))
+            }
+            _ if nav == Some(Action::PageUp) => view.scroll = view.scroll.saturating_sub(20),
+            _ if nav == Some(Action::First) => view.scroll = 0,
+            _ => {}
+        }
+    } else if let Some(view) = &mut state.smaps {
+        let len = view.groups.as_ref().map_or(0, |g| g.len());
+        match key.code {
+            KeyCode::Esc | KeyCode::Enter => state.smaps = None,
+            _ if nav == Some(Action::Down) => {
+                view.scroll = (view.scroll + 1).min(len.saturating_sub(1))
+            }
+            _ if nav == Some(Action::Up) => view.scroll = view.scroll.saturating_sub(1),
+            _ if nav == Some(Action::PageDown) => {
+                view.scroll = (view.scroll + 20).min(len.saturating_sub(1))
+            }
+            _ if nav == Some(Action::PageUp) => view.scroll = view.scroll.saturating_sub(20),
+            _ if nav == Some(Action::First) => view.scroll = 0,
+            _ => {}
+        }
+    } else if let Some(selected) = &mut state.column_picker {
+        let columns = picker_columns(&state.visible.columns);
+        //the tree is drawn in the command column
+        let keep = state.visible.is_tree().then_some(Column::Command);
+        let visible = &mut state.visible.columns;
+        let (col, shown) = columns[*selected];
+        match key.code {
+            KeyCode::Esc | KeyCode::Enter => state.column_picker = None,
+            _ if nav == Some(Action::Down) => *selected = (*selected + 1).min(columns.len() - 1),
+            _ if nav == Some(Action::Up) => *selected = selected.saturating_sub(1),
+            //at least one column must stay visible
+            KeyCode::Char(' ') if shown && visible.len() > 1 && keep != Some(col) => {
+                visible.retain(|c| *c != col)
+            }
+            KeyCode::Char(' ') if !shown => {
+                visible.push(col);
+                *selected = visible.len() - 1;
+            }
+            KeyCode::Char('<') if shown && *selected > 0 => {
+                visible.swap(*selected, *selected - 1);
+                *selected -= 1;
+            }
+            KeyCode::Char('>') if shown && *selected + 1 < visible.len() => {
+                visible.swap(*selected, *selected + 1);
+                *selected += 1;
+            }
+            _ => {}
+        }
+        state.sort();
+    } else if let Some(edit) = &mut state.affinity {
+        let count = edit.cores.len();
+        match key.code {
+            _ if nav == Some(Action::SortLeft) => edit.core = edit.core.saturating_sub(1),
+            _ if nav == Some(Action::SortRight) => {
+                edit.core = (edit.core + 1).min(count.saturating_sub(1))
+            }
+            _ if nav == Some(Action::Up) && edit.core >= 4 => edit.core -= 4,
+            _ if nav == Some(Action::Down) && edit.core + 4 < count => edit.core += 4,
+            KeyCode::Char(' ') => {
+                if let Some(core) = edit.cores.get_mut(edit.core) {
+                    *core = !*core;
                 }
-                KeyCode::Right | KeyCode::Char('l') => {
-                    state.visible.sort_col = (state.visible.sort_col + 1).min(7);
-                    if state.visible.sort_col > 0 && state.visible.sort_type == SortType::None {
-                        state.visible.sort_type = SortType::Descending;
+            }
+            KeyCode::Char('a') => edit.cores.iter_mut().for_each(|c| *c = true),
+            KeyCode::Char('t') => edit.tree = !edit.tree,
+            KeyCode::Esc => state.affinity = None,
+            KeyCode::Enter if !state.procs.contains_key(&edit.proc.id()) => {
+                edit.error = Some("process no longer exists".to_string());
+            }
+            KeyCode::Enter => {
+                let pids = if edit.tree {
+                    with_descendants(edit.proc.pid, &state.procs)
+                } else {
+                    vec![edit.proc.pid]
+                };
+                let result = if edit.cores.iter().any(|c| *c) {
+                    //apply to every process, so one that exited does not stop the rest
+                    let errors: Vec<String> = pids
+                        .iter()
+                        .filter_map(|pid| {
+                            affinity::set_affinity(*pid, &edit.cores)
+                                .err()
+                                .map(|err| format!("{pid}: {err}"))
+                        })
+                        .collect();
+                    match errors.len() {
+                        0 => Ok(()),
+                        n => Err(format!(
+                            "failed for {n} of {} processes, {}",
+                            pids.len(),
+                            errors.join(", ")
+                        )),
                     }
-                    state.sort();
-                }
-                KeyCode::Char('t') => {
-                    state.visible.sort_col = 0;
-                    state.visible.sort_type = SortType::None;
-                    state.sort();
-                }
-                KeyCode::Char('-') => {
-                    state.config.tui.update_interval =
-                        (state.config.tui.update_interval - 0.5).max(0.5);
+                } else {
+                    Err("at least one core must be allowed".to_string())
+                };
+                match result {
+                    Ok(()) => state.affinity = None,
+                    Err(err) => edit.error = Some(err),
                 }
-                KeyCode::Char('+') => {
-                    state.config.tui.update_interval += 0.5;
+            }
+            _ => {}
+        }
+    } else if let Some(action) = action {
+        handle_action(action, done, state);
+    } else if let KeyCode::Char(c) = key.code
+        && let Some(n) = c.to_digit(10)
+    {
+        let view = match n as usize {
+            0 => Some(View::default()),
+            n => state.config.views.get(n - 1).cloned(),
+        };
+        if let Some(view) = view {
+            state.visible.apply_view(&view);
+            state.filter = view.filter.clone();
+            state.view = Some(view.name).filter(|name| !name.is_empty());
+            state.sort();
+        }
+    }
+}
+
+fn handle_action(action: Action, done: &mut bool, state: &mut State) {
+    match action {
+        Action::Quit => *done = true,
+        Action::SortCycle => {
+            state.visible.sort_cycle();
+            state.sort();
+        }
+        Action::ToggleCores => {
+            state.config.tui.show_cpu_per_core = !state.config.tui.show_cpu_per_core;
+        }
+        Action::Filter => {
+            state.filtering = !state.filtering;
+        }
+        Action::ToggleDisks => state.config.tui.show_disks = !state.config.tui.show_disks,
+        Action::Collapse => {
+            if state.visible.is_tree()
+                && let Some(proc) = process_at_selected(state)
+            {
+                state.visible.hide_children_invert(proc.id());
+            }
+        }
+        Action::CollapseAll | Action::ExpandAll | Action::CollapseLevel | Action::ExpandLevel
+            if state.visible.is_tree() =>
+        {
+            let depth = state.visible.visible_depth();
+            match action {
+                Action::CollapseAll => state.visible.collapse_all(),
+                Action::ExpandAll => state.visible.expand_all(),
+                Action::CollapseLevel => state
+                    .visible
+                    .collapse_to_depth(depth.saturating_sub(1).max(1)),
+                _ => state.visible.collapse_to_depth(depth + 1),
+            }
+        }
+        Action::ExpandToSelected => {
+            if state.visible.is_tree()
+                && let Some(proc) = process_at_selected(state)
+            {
+                state.visible.expand_to(proc.id());
+            }
+        }
+        Action::AutoCollapse => {
+            state.visible.auto_collapse = match state.visible.auto_collapse {
+                Some(_) => None,
+                None => Some(state.auto_collapse_below()),
+            };
+            state.sort();
+        }
+        Action::CollapseAll | Action::ExpandAll | Action::CollapseLevel | Action::ExpandLevel => {}
+        Action::Follow => match state.selected {
+            Selected::Index(_) => {
+                if let Some(proc) = process_at_selected(state) {
+                    state.selected = Selected::Proc(proc.id());
                 }
-                _ => {}
             }
+            Selected::Proc(id) => {
+                state.selected = Selected::Index(state.visible.position(&id).unwrap_or(0));
+            }
+        },
+        Action::Search => {
+            state.searching = true;
+        }
+        Action::HideZeros => state.visible.hidezeros = !state.visible.hidezeros,
+        Action::SaveView => {
+            state.naming_view = Some(state.view.clone().unwrap_or_default());
+        }
+        Action::Ancestors => {
+            state.visible.keep_ancestors = !state.visible.keep_ancestors;
+            state.sort();
+        }
+        Action::KernelThreads => {
+            state.visible.hide_kernel_threads = !state.visible.hide_kernel_threads;
+            state.sort();
+        }
+        Action::NextTheme => state.next_theme(),
+        Action::StateFilter => {
+            //cycle through the states, and then no filter
+            state.visible.state_filter = match state.visible.state_filter {
+                None => Some(ProcState::ALL[0]),
+                Some(current) => ProcState::ALL
+                    .iter()
+                    .skip_while(|s| **s != current)
+                    .nth(1)
+                    .copied(),
+            };
+            state.sort();
+        }
+        Action::TreeTotals => {
+            state.visible.tree_totals = !state.visible.tree_totals;
+            state.sort();
+        }
+        Action::Help => state.show_help = !state.show_help,
+        Action::Gui => {
+            *done = true;
+            state.start_gui = true
+        }
+        Action::Kill => {
+            state.show_kill = !state.show_kill;
+            let proc = process_at_selected(state);
+            state.kill_process = proc;
+        }
+        Action::Stop | Action::StopTree => {
+            if let Some(proc) = process_at_selected(state) {
+                state.send_signal(proc.id(), SIGSTOP, action == Action::StopTree);
+            }
+        }
+        Action::Continue | Action::ContinueTree => {
+            if let Some(proc) = process_at_selected(state) {
+                state.send_signal(proc.id(), SIGCONT, action == Action::ContinueTree);
+            }
+        }
+        Action::MemoryMap => {
+            if let Some(proc) = process_at_selected(state) {
+                state.smaps = Some(SmapsView {
+                    groups: smaps::load(proc.pid),
+                    proc,
+                    scroll: 0,
+                });
+            }
+        }
+        Action::Threads => {
+            if let Some(proc) = process_at_selected(state) {
+                state.threads = Some(ThreadsView {
+                    proc,
+                    threads: None,
+                    scroll: 0,
+                });
+            }
+        }
+        Action::Affinity => {
+            if let Some(proc) = process_at_selected(state) {
+                let count = state.totals.cpus.len();
+                let (cores, error) = match affinity::get_affinity(proc.pid, count) {
+                    Some(cores) => (cores, None),
+                    None => (
+                        vec![true; count],
+                        Some("unable to read affinity".to_string()),
+                    ),
+                };
+                state.affinity = Some(AffinityEdit {
+                    proc,
+                    cores,
+                    core: 0,
+                    tree: false,
+                    error,
+                });
+            }
+        }
+        Action::Down => move_by(state, 1),
+        Action::Up => move_by(state, -1),
+        Action::PageDown => move_by(state, 20),
+        Action::PageUp => move_by(state, -20),
+        Action::First => move_by(state, isize::MIN),
+        Action::Last => move_by(state, isize::MAX),
+        Action::Info | Action::OpenFiles | Action::Ancestry => {
+            if let Some(proc) = process_at_selected(state) {
+                state.show_info = Some(proc.id());
+                state.info_scroll = 0;
+                state.info_tab = match action {
+                    Action::Info => InfoTab::Details,
+                    Action::OpenFiles => InfoTab::Files,
+                    _ => InfoTab::Ancestry,
+                };
+            }
+        }
+        Action::Family => {
+            state.visible.family = match state.visible.family {
+                Some(_) => None,
+                None => process_at_selected(state).map(|p| p.id()),
+            };
+            state.sort();
+        }
+        Action::SortLeft => {
+            let pos = state.sort_position().saturating_sub(1);
+            state.visible.sort_col = state.visible.columns[pos];
+            if pos == 0 {
+                state.visible.sort_type = SortType::None;
+            }
+            state.sort();
+        }
+        Action::SortRight => {
+            let pos = (state.sort_position() + 1).min(state.visible.columns.len() - 1);
+            state.visible.sort_col = state.visible.columns[pos];
+            if pos > 0 && state.visible.sort_type == SortType::None {
+                state.visible.sort_type = SortType::Descending;
+            }
+            state.sort();
+        }
+        Action::Columns => state.column_picker = Some(0),
+        Action::Tree => {
+            state.visible.sort_col = Column::Command;
+            state.visible.sort_type = SortType::None;
+            state.sort();
+        }
+        Action::TreeMode => {
+            state.visible.tree_mode = !state.visible.tree_mode;
+            state.sort();
+        }
+        Action::Faster => {
+            state.config.tui.update_interval = (state.config.tui.update_interval - 0.5).max(0.5);
+        }
+        Action::Slower => {
+            state.config.tui.update_interval += 0.5;
         }
     }
 }
 fn index_of_selected(state: &State) -> Option<usize> {
     match state.selected {
         Selected::Index(ind) => Some(ind),
-        Selected::Proc(pid) => state
-            .visible
-            .procs()
-            .iter()
-            .position(|a| a[2].parse::<usize>() == Ok(pid)),
+        Selected::Proc(id) => state.visible.position(&id),
     }
 }
 fn process_at_selected(state: &State) -> Option<MyProcess> {
     match state.selected {
         Selected::Index(ind) => state
             .visible
-            .procs()
+            .rows()
             .get(ind)
-            .and_then(|a| a.get(2))
-            .and_then(|pid_s| state.procs.get(&pid_s.parse().unwrap_or_default()).cloned()),
-        Selected::Proc(pid) => state.procs.get(&pid).cloned(),
+            .and_then(|row| state.procs.get(&row.proc.id()).cloned()),
+        Selected::Proc(id) => state.procs.get(&id).cloned(),
     }
 }
 
@@ -795,74 +1961,197 @@ fn move_by(state: &mut State, by: isize) {
     match state.selected {
         Selected::Index(ind) => {
             state.selected = Selected::Index(
-                (ind.saturating_add_signed(by)).min(state.visible.procs().len().saturating_sub(1)),
+                (ind.saturating_add_signed(by)).min(state.visible.rows().len().saturating_sub(1)),
             );
         }
-        Selected::Proc(pid) => {
-            let mut ind = state
-                .visible
-                .procs()
-                .iter()
-                .position(|a| a[2].parse::<usize>().unwrap_or_default() == pid)
-                .unwrap_or(0);
-            ind = ind
+        Selected::Proc(id) => {
+            let rows = state.visible.rows();
+            if rows.is_empty() {
+                return;
+            }
+            let ind = state.visible.position(&id).unwrap_or(0);
+            let ind = ind
                 .saturating_add_signed(by)
-                .min(state.visible.procs().len() - 1);
-            state.selected = Selected::Proc(
-                state.visible.procs()[ind][2]
-                    .parse::<usize>()
-                    .unwrap_or_default(),
-            );
+                .min(rows.len().saturating_sub(1));
+            state.selected = Selected::Proc(rows[ind].proc.id());
         }
     }
 }
 
 impl State {
+    fn auto_collapse_below(&self) -> u64 {
+        self.config
+            .tui
+            .auto_collapse_below_mb
+            .saturating_mul(1024 * 1024)
+    }
+
+    ///cycles built in themes, then user themes
+    fn next_theme(&mut self) {
+        let names: Vec<String> = Theme::NAMES
+            .iter()
+            .map(|n| n.to_string())
+            .chain(self.config.themes.keys().cloned())
+            .collect();
+        let current = names
+            .iter()
+            .position(|n| *n == self.config.theme)
+            .unwrap_or_default();
+        self.config.theme = names[(current + 1) % names.len()].clone();
+        self.theme = self.config.theme();
+    }
+
     fn sort(&mut self) {
         let mut temp: Vec<_> = self.procs.values().cloned().collect();
-        self.visible.update(&temp);
+        self.visible.update(&with_exited(&self.procs, &self.exited));
 
         //find top 5
         temp.sort_by_key(|a| Reverse(a.memory));
-        self.top5memory = temp.iter().map(|f| f.pid).take(5).collect();
+        self.top5memory = temp.iter().map(|f| f.id()).take(5).collect();
 
         temp.sort_by(|a, b| b.cpu.total_cmp(&a.cpu));
-        self.top5cpu = temp.iter().map(|f| f.pid).take(5).collect();
+        self.top5cpu = temp.iter().map(|f| f.id()).take(5).collect();
+    }
+
+    ///position of the sort column in the visible columns
+    fn sort_position(&self) -> usize {
+        self.visible
+            .columns
+            .iter()
+            .position(|c| *c == self.visible.sort_col)
+            .unwrap_or(0)
+    }
+
+    ///live process with this pid
+    ///lines of the selected info tab, empty if the process exited
+    fn info_lines(&self) -> Vec<String> {
+        let Some(proc) = self.show_info.and_then(|id| self.procs.get(&id)) else {
+            return vec![];
+        };
+        match self.info_tab {
+            InfoTab::Details => {
+                let parent = self.find_pid(proc.parent).map_or("", |p| p.name.as_str());
+                details_lines(proc, parent, self)
+            }
+            InfoTab::Files => files_lines(self),
+            InfoTab::Ancestry => ancestry_lines(proc, self),
+        }
+    }
+
+    ///the last line stays visible
+    fn info_scroll_max(&self) -> u16 {
+        self.info_lines()
+            .len()
+            .saturating_sub(1)
+            .min(u16::MAX as usize) as u16
+    }
+
+    fn find_pid(&self, pid: usize) -> Option<&MyProcess> {
+        self.procs.values().find(|p| p.pid == pid)
+    }
+
+    ///sends signal to the process, and if tree is set to all its descendants too
+    ///nothing is sent if the process exited, as its pid could be reused by another process
+    fn send_signal(&self, id: ProcId, signal: &str, tree: bool) {
+        if !self.procs.contains_key(&id) {
+            return;
+        }
+        let pids = if tree {
+            with_descendants(id.pid, &self.procs)
+        } else {
+            vec![id.pid]
+        };
+        for pid in pids {
+            let _ = Command::new("kill")
+                .arg(format!("-{signal}"))
+                .arg(format!("{pid}"))
+                .output();
+        }
+    }
+
+    ///open files are only loaded while the files tab is visible
+    fn load_open_files(&mut self) {
+        self.open_files = match (self.show_info, self.info_tab) {
+            (Some(id), InfoTab::Files) => Some(openfiles::load(id.pid)),
+            _ => None,
+        };
     }
 
     fn update_search(&mut self) {
         let search_lower = self.search.to_lowercase();
         //we find the first process matching the search string
-        if let Some(proc) = self
-            .visible
-            .procs()
-            .iter()
-            .find(|a| a.iter().any(|a| a.to_lowercase().contains(&search_lower)))
-        {
-            self.selected = Selected::Proc(proc[2].parse::<usize>().unwrap_or_default());
+        //only the visible columns are searched
+        if let Some(row) = self.visible.rows().iter().find(|row| {
+            self.visible.columns.iter().any(|col| {
+                self.visible
+                    .format(row, *col)
+                    .to_lowercase()
+                    .contains(&search_lower)
+            })
+        }) {
+            self.selected = Selected::Proc(row.proc.id());
         }
     }
 }
 
+///live and exited processes, exited are attached to the root if their parent is gone as well
+///selection and actions find processes by pid, so exited processes whose pid was reused are left out
+fn with_exited(
+    procs: &HashMap<ProcId, MyProcess>,
+    exited: &HashMap<ProcId, MyProcess>,
+) -> Vec<MyProcess> {
+    let mut result: Vec<MyProcess> = procs.values().cloned().collect();
+    let live: HashSet<usize> = procs.keys().map(|id| id.pid).collect();
+    //if a pid was reused more than once, only the last exited process is kept
+    let mut latest: HashMap<usize, &MyProcess> = HashMap::new();
+    for proc in exited.values().filter(|p| !live.contains(&p.pid)) {
+        let entry = latest.entry(proc.pid).or_insert(proc);
+        if proc.start_time > entry.start_time {
+            *entry = proc;
+        }
+    }
+    let kept: HashSet<ProcId> = latest.values().map(|p| p.id()).collect();
+    for proc in latest.values() {
+        let mut proc = (*proc).clone();
+        let parent = proc.parent_id();
+        if !procs.contains_key(&parent) && !kept.contains(&parent) {
+            proc.parent = 0;
+            proc.parent_start_time = 0;
+        }
+        result.push(proc);
+    }
+    result
+}
+
+///returns the pid and all its descendants
+fn with_descendants(pid: usize, procs: &HashMap<ProcId, MyProcess>) -> Vec<usize> {
+    let snapshot = Snapshot::from_procs(procs.values().cloned().collect());
+    let descendants = snapshot.descendants(pid).into_iter().map(|p| p.pid);
+    std::iter::once(pid).chain(descendants).collect()
+}
+
 use std::{
     cmp::Reverse,
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     process::Command,
     time::{Duration, Instant},
 };
 
 use crossterm::{
     event::{
-        self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEventKind, KeyModifiers,
+        self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent, KeyEventKind,
+        MouseButton, MouseEvent, MouseEventKind,
     },
     execute,
-    terminal::{EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode},
+    terminal::{
+        self, EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode,
+    },
 };
 use ratatui::{
     Frame, Terminal,
     backend::CrosstermBackend,
-    layout::{Constraint, Rect},
-    style::Style,
+    layout::{Constraint, Layout, Rect},
+    style::{Modifier, Style},
     text::{Line, Span},
     widgets::{
         Block, BorderType, Borders, Cell, Clear, LineGauge, Padding, Paragraph, Row, Table,
@@ -871,10 +2160,48 @@ use ratatui::{
 };
 
 use crate::{
+    affinity,
     config::Config,
-    helpers::{nice_size, nice_size_g, nice_size_ops, nice_time},
-    manager::{self, Totals},
-    mynetwork::MyNetwork,
-    myprocess::MyProcess,
-    sorted::{SortType, SortedProcesses},
+    keymap::{Action, Keymap},
+    openfiles::{self, OpenFd},
+    smaps::{self, MappingGroup},
+    theme::Theme,
+};
+use toprs::{
+    Churn, Column, Metric, MyDisk, MyNetwork, MyProcess, MyThread, ProcId, ProcManager, ProcState,
+    ProcessDetails, Snapshot, SortType, SortedProcesses, Totals, View,
+    helpers::{nice_size, nice_size_g, nice_size_ops, nice_time, nice_timestamp},
 };
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_with_exited() {
+        let proc = |pid, (parent, parent_start_time), start_time| {
+            let mut proc = MyProcess::default();
+            proc.pid = pid;
+            proc.parent = parent;
+            proc.parent_start_time = parent_start_time;
+            proc.start_time = start_time;
+            proc
+        };
+        let by_id = |procs: Vec<MyProcess>| -> HashMap<ProcId, MyProcess> {
+            procs.into_iter().map(|p| (p.id(), p)).collect()
+        };
+        //5 exited and its pid is now used by a live process, 6 exited and its parent 5 is gone
+        let procs = by_id(vec![proc(1, (0, 0), 10), proc(5, (1, 10), 30)]);
+        let exited = by_id(vec![
+            proc(5, (1, 10), 20),
+            proc(6, (5, 20), 21),
+            proc(7, (1, 10), 22),
+        ]);
+        let mut result: Vec<(usize, usize, u64)> = with_exited(&procs, &exited)
+            .iter()
+            .map(|p| (p.pid, p.parent, p.start_time))
+            .collect();
+        result.sort();
+        assert_eq!(result, vec![(1, 0, 10), (5, 1, 30), (6, 0, 21), (7, 1, 22)]);
+    }
+}